@@ -0,0 +1,71 @@
+use criterion::*;
+use pcg_mwc::{
+    Mwc1024XXA64, Mwc128XXA32, Mwc128XXA32x8, Mwc256XXA64, Mwc256XXA64x4, Mwc256XXA64x8, Mwc320XXA64,
+};
+use rand_core::{RngCore, SeedableRng};
+
+fn bench_mwc_64(c: &mut Criterion) {
+    let mut mwc = Mwc256XXA64::seed_from_u64(2);
+    c.bench_function("Mwc256XXA64/64", move |b| b.iter(|| mwc.next_u64()));
+}
+
+fn bench_mwc320_64(c: &mut Criterion) {
+    let mut mwc = Mwc320XXA64::seed_from_u64(2);
+    c.bench_function("Mwc320XXA64/64", move |b| b.iter(|| mwc.next_u64()));
+}
+
+fn bench_mwc1024_64(c: &mut Criterion) {
+    let mut mwc = Mwc1024XXA64::seed_from_u64(2);
+    c.bench_function("Mwc1024XXA64/64", move |b| b.iter(|| mwc.next_u64()));
+}
+
+fn bench_mwc_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Mwc256XXA64 1mb");
+    group.throughput(Throughput::Bytes(1 << 20));
+    let mut bytes = vec![0u8; 1 << 20];
+    let mut words = vec![0u64; 1 << 17];
+    let mut mwc = Mwc256XXA64::seed_from_u64(2);
+    group.bench_function("fill_bytes", |b| b.iter(|| mwc.fill_bytes(&mut bytes)));
+    group.bench_function("fill_u64", |b| b.iter(|| mwc.fill_u64(&mut words)));
+    let mut mwc320 = Mwc320XXA64::seed_from_u64(2);
+    group.bench_function("Mwc320XXA64 fill_u64", |b| b.iter(|| mwc320.fill_u64(&mut words)));
+    let mut mwc1024 = Mwc1024XXA64::seed_from_u64(2);
+    group.bench_function("Mwc1024XXA64 fill_u64", |b| b.iter(|| mwc1024.fill_u64(&mut words)));
+    let mut x4 = Mwc256XXA64x4::seed_from_u64(2);
+    group.bench_function("x4 fill_bytes", |b| b.iter(|| x4.fill_bytes(&mut bytes)));
+    group.bench_function("x4 fill_u64", |b| b.iter(|| x4.fill_u64(&mut words)));
+    let mut x8 = Mwc256XXA64x8::seed_from_u64(2);
+    group.bench_function("x8 fill_bytes", |b| b.iter(|| x8.fill_bytes(&mut bytes)));
+    group.bench_function("x8 fill_u64", |b| b.iter(|| x8.fill_u64(&mut words)));
+    group.finish();
+}
+
+fn bench_mwc32_32(c: &mut Criterion) {
+    let mut mwc = Mwc128XXA32::seed_from_u64(2);
+    c.bench_function("Mwc128XXA32/32", move |b| b.iter(|| mwc.next_u32()));
+}
+
+fn bench_mwc32_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Mwc128XXA32 1mb");
+    group.throughput(Throughput::Bytes(1 << 20));
+    let mut bytes = vec![0u8; 1 << 20];
+    let mut words = vec![0u32; 1 << 18];
+    let mut mwc = Mwc128XXA32::seed_from_u64(2);
+    group.bench_function("fill_bytes", |b| b.iter(|| mwc.fill_bytes(&mut bytes)));
+    group.bench_function("fill_u32", |b| b.iter(|| mwc.fill_u32(&mut words)));
+    let mut x8 = Mwc128XXA32x8::seed_from_u64(2);
+    group.bench_function("x8 fill_bytes", |b| b.iter(|| x8.fill_bytes(&mut bytes)));
+    group.bench_function("x8 fill_u32", |b| b.iter(|| x8.fill_u32(&mut words)));
+    group.finish();
+}
+
+criterion_main!(benches);
+criterion_group!(
+    benches,
+    bench_mwc_64,
+    bench_mwc320_64,
+    bench_mwc1024_64,
+    bench_mwc_fill,
+    bench_mwc32_32,
+    bench_mwc32_fill
+);
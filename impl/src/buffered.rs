@@ -0,0 +1,112 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::fmt;
+use rand_core::{Error, RngCore, SeedableRng};
+
+use crate::Mwc256XXA64;
+
+/// A `Mwc256XXA64` which uses both halves of each output for `next_u32`.
+///
+/// `Mwc256XXA64::next_u32` discards the upper half of an output. This wrapper instead keeps it, and returns it on
+/// the following call, so drawing `u32`s (or `f32`s) takes half as many steps.
+///
+/// Output order: `next_u32` returns the low half of an output followed by its high half, which is the same order
+/// as `Mwc256XXA64::fill_u32` (and `fill_bytes` read as little endian words). `next_u64` and `fill_bytes` always use
+/// fresh outputs, and leave a pending high half in place for the next call to `next_u32`.
+/// The pending half is part of the state, so it is preserved by `clone` and by serialization.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BufferedMwc256XXA64 {
+    pub(crate) rng: Mwc256XXA64,
+    pub(crate) pending: Option<u32>,
+}
+
+impl BufferedMwc256XXA64 {
+    /// Returns the wrapped generator. Any pending half output is discarded.
+    pub fn into_inner(self) -> Mwc256XXA64 {
+        self.rng
+    }
+}
+
+impl From<Mwc256XXA64> for BufferedMwc256XXA64 {
+    fn from(rng: Mwc256XXA64) -> Self {
+        BufferedMwc256XXA64 { rng, pending: None }
+    }
+}
+
+// Custom Debug implementation that does not expose the internal state
+impl fmt::Debug for BufferedMwc256XXA64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BufferedMwc256XXA64 {{}}")
+    }
+}
+
+impl SeedableRng for BufferedMwc256XXA64 {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Mwc256XXA64::from_seed(seed).into()
+    }
+}
+
+impl RngCore for BufferedMwc256XXA64 {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        match self.pending.take() {
+            Some(high) => high,
+            None => {
+                let value = self.rng.next_u64();
+                self.pending = Some((value >> 32) as u32);
+                value as u32
+            }
+        }
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    #[inline(always)]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_uses_both_halves() {
+        let mut buffered = BufferedMwc256XXA64::seed_from_u64(14);
+        let mut words = [0u32; 101];
+        Mwc256XXA64::seed_from_u64(14).fill_u32(&mut words);
+        for &word in words.iter() {
+            assert_eq!(buffered.next_u32(), word);
+        }
+    }
+
+    #[test]
+    fn test_pending_half_is_kept() {
+        let mut buffered = BufferedMwc256XXA64::seed_from_u64(15);
+        let mut rng = Mwc256XXA64::seed_from_u64(15);
+        let first = rng.next_u64();
+        let second = rng.next_u64();
+        assert_eq!(buffered.next_u32(), first as u32);
+        let copy = buffered.clone();
+        assert_eq!(buffered.next_u64(), second);
+        assert_eq!(buffered.next_u32(), (first >> 32) as u32);
+        assert_ne!(copy, buffered);
+        assert_eq!(buffered.into_inner(), rng);
+    }
+}
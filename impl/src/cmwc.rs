@@ -0,0 +1,157 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Complementary multiply with carry siblings of `Mwc256XXA64` and `Mwc128XXA32`.
+
+use rand_core::{le, SeedableRng};
+
+use crate::mwc::Mwc;
+
+// `MULTIPLIER * 2^192 + 1` is prime and `2^64` has the largest possible order modulo it.
+// Found with `gen_cmwc_candidates` in `parm_search.rs`.
+pub(crate) const MULTIPLIER_64: u64 = 0xffaf_d1cb_b35a_adb7;
+// `MULTIPLIER * 2^96 + 1` is prime and `2^32` has the largest possible order modulo it.
+pub(crate) const MULTIPLIER_32: u32 = 0xff56_955d;
+
+/// A permuted complementary multiply with carry generator with `LAG` words of type `W`.
+///
+/// This is Marsaglia's CMWC recurrence with base `2^W::BITS`: each step computes `t = MULTIPLIER * oldest + c`,
+/// the new carry is the high word of `t`, and the new word is the complement of its low word. Apart from that
+/// complement it is the same generator as `Mwc`, with the same output permutation and methods.
+///
+/// The generator is equivalent to a MCG with the modulus `p = MULTIPLIER * 2^(W::BITS * LAG) + 1`, which must be
+/// prime, and its period is the order of `2^W::BITS` modulo `p`. Because 2 is a square modulo `p`, the order is at most
+/// `(p - 1) / (2 * W::BITS)`, which is a little shorter than the period of the `Mwc` generator of the same size.
+/// Unlike `Mwc` there are no fixed points: the `p - 1` states with a carry less than `MULTIPLIER` split evenly into
+/// `2 * W::BITS` cycles. (`parm_search.rs` in the repository searches for multipliers which reach that period.)
+pub type Cmwc<W, const LAG: usize, const MULTIPLIER: u64> = Mwc<W, LAG, MULTIPLIER, true>;
+
+/// A CMWC sibling of `Mwc256XXA64`, with a period of about `2^249`.
+pub type Cmwc256XXA64 = Cmwc<u64, 3, MULTIPLIER_64>;
+
+/// A CMWC sibling of `Mwc128XXA32`, with a period of about `2^122`.
+pub type Cmwc128XXA32 = Cmwc<u32, 3, { MULTIPLIER_32 as u64 }>;
+
+/// We use a single 255-bit seed to initialise the state and select a stream.
+impl SeedableRng for Cmwc256XXA64 {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_u64 = [0u64; 4];
+        le::read_u64_into(&seed, &mut seed_u64);
+        // c must be < MULTIPLE, but any words are valid
+        let c = seed_u64[0] & 0x7fff_ffff_ffff_ffff;
+        Cmwc256XXA64::from_state_incr([seed_u64[1], seed_u64[2], seed_u64[3]], c)
+    }
+}
+
+/// We use a single 127-bit seed to initialise the state and select a stream.
+impl SeedableRng for Cmwc128XXA32 {
+    type Seed = [u8; 16];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_u32 = [0u32; 4];
+        le::read_u32_into(&seed, &mut seed_u32);
+        // c must be < MULTIPLE, but any words are valid
+        let c = seed_u32[0] & 0x7fff_ffff;
+        Cmwc128XXA32::from_state_incr([seed_u32[1], seed_u32[2], seed_u32[3]], c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    use crate::mwc::test::{check_outputs_differ, check_seeded_states_valid};
+    use crate::InvalidState;
+
+    /// Returns the lengths of the cycles through all `n` states, where `next` maps the index of a state to the
+    /// index of the one following it.
+    fn cycle_lengths(n: usize, mut next: impl FnMut(usize) -> usize) -> Vec<usize> {
+        let mut seen = vec![false; n];
+        let mut lengths = Vec::new();
+        for start in 0..n {
+            let (mut state, mut length) = (start, 0);
+            while !seen[state] {
+                seen[state] = true;
+                state = next(state);
+                length += 1;
+            }
+            if length > 0 {
+                assert_eq!(state, start, "states must form cycles");
+                lengths.push(length);
+            }
+        }
+        lengths.sort_unstable();
+        lengths
+    }
+
+    #[test]
+    fn test_period_structure() {
+        // `12 * 2^16 + 1` is prime and `2^8` has order `12 * 2^12` modulo it.
+        let lengths = cycle_lengths(12 << 16, |i| {
+            let mut rng = Cmwc::<u8, 2, 12> { x: [i as u8, (i >> 8) as u8], c: (i >> 16) as u8 };
+            rng.step();
+            rng.x[0] as usize | (rng.x[1] as usize) << 8 | (rng.c as usize) << 16
+        });
+        assert_eq!(lengths, vec![12 << 12; 16]);
+
+        // `45 * 2^16 - 1` is a safe prime. Both of the fixed points are among these states.
+        let lengths = cycle_lengths(45 << 16, |i| {
+            let mut rng = Mwc::<u8, 2, 45> { x: [i as u8, (i >> 8) as u8], c: (i >> 16) as u8 };
+            rng.step();
+            rng.x[0] as usize | (rng.x[1] as usize) << 8 | (rng.c as usize) << 16
+        });
+        assert_eq!(lengths, vec![1, 1, ((45 << 16) - 2) / 2, ((45 << 16) - 2) / 2]);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut rng = Cmwc256XXA64::seed_from_u64(1);
+        for _ in 0..1000 {
+            let ([x1, x2, x3], c) = rng.to_words();
+            let product = x3 as u128 * MULTIPLIER_64 as u128;
+            let t = product + c as u128;
+            let expected_output = (x3 ^ x2).wrapping_add(x1 ^ (product >> 64) as u64);
+            assert_eq!(rng.next_u64(), expected_output);
+            assert_eq!(rng.to_words(), ([!(t as u64), x1, x2], (t >> 64) as u64));
+        }
+    }
+
+    #[test]
+    fn test_seeded_states_are_valid() {
+        check_seeded_states_valid::<Cmwc256XXA64>();
+        check_seeded_states_valid::<Cmwc128XXA32>();
+    }
+
+    #[test]
+    fn test_try_from_words() {
+        assert_eq!(Cmwc128XXA32::try_from_words([0; 3], MULTIPLIER_32), Err(InvalidState::CarryTooLarge));
+        // There are no fixed points to reject.
+        assert!(Cmwc128XXA32::try_from_words([0; 3], 0).is_ok());
+        assert!(Cmwc128XXA32::try_from_words([u32::MAX; 3], MULTIPLIER_32 - 1).is_ok());
+    }
+
+    #[test]
+    fn test_seeds_differ() {
+        check_outputs_differ(Cmwc256XXA64::seed_from_u64(1), Cmwc256XXA64::seed_from_u64(2));
+        check_outputs_differ(Cmwc128XXA32::seed_from_u64(1), Cmwc128XXA32::seed_from_u64(2));
+    }
+
+    #[test]
+    fn test_next_u64_word_order() {
+        let mut rng = Cmwc128XXA32::seed_from_u64(1);
+        let [first, second] = rng.clone().gen_array::<2>();
+        assert_eq!(rng.next_u64(), (first as u64) << 32 | second as u64);
+    }
+
+    #[test]
+    fn test_debug() {
+        let (a, b) = (Cmwc256XXA64::seed_from_u64(1), Cmwc128XXA32::seed_from_u64(1));
+        assert_eq!(format!("{:?} {:?}", a, b), "Cmwc256XXA64 {} Cmwc128XXA32 {}");
+    }
+}
@@ -0,0 +1,72 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Mwc256XXA64;
+
+/// Creates generators for parallel tasks which are guaranteed not to overlap.
+///
+/// Stream `i` is the root generator advanced by `i * 2^stream_bits` steps, so as long as no stream draws more
+/// than `2^stream_bits` values (where every `next_u64` or `next_u32` call, and every 8 bytes of `fill_bytes`,
+/// is one draw) the streams are disjoint parts of the same sequence. With the default of 64 bits there are
+/// `2^64` streams of `2^64` draws each, which together use only `2^128` of the roughly `2^255` period.
+///
+/// The streams only depend on the root generator and their index, so they are reproducible
+/// regardless of the order in which they are created.
+#[derive(Clone, Debug)]
+pub struct StreamFactory {
+    root: Mwc256XXA64,
+    stream_bits: u32,
+}
+
+impl StreamFactory {
+    /// The number of draws per stream used by `new` is `2^DEFAULT_STREAM_BITS`.
+    pub const DEFAULT_STREAM_BITS: u32 = 64;
+
+    /// Creates a factory where each stream may draw up to `2^64` values.
+    pub fn new(root: Mwc256XXA64) -> Self {
+        StreamFactory::with_stream_bits(root, Self::DEFAULT_STREAM_BITS)
+    }
+
+    /// Creates a factory where each stream may draw up to `2^stream_bits` values.
+    ///
+    /// Panics if `stream_bits` is greater than 64.
+    pub fn with_stream_bits(root: Mwc256XXA64, stream_bits: u32) -> Self {
+        assert!(stream_bits <= 64, "stream_bits must be at most 64");
+        StreamFactory { root, stream_bits }
+    }
+
+    /// The number of values each stream can draw before it may overlap the next one.
+    pub fn max_draws_per_stream(&self) -> u128 {
+        1 << self.stream_bits
+    }
+
+    /// Returns the generator for stream `index`.
+    pub fn stream(&self, index: u64) -> Mwc256XXA64 {
+        let mut rng = self.root.clone();
+        rng.advance((index as u128) << self.stream_bits);
+        rng
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::{RngCore, SeedableRng};
+
+    #[test]
+    fn test_streams_are_consecutive() {
+        let root = Mwc256XXA64::seed_from_u64(9);
+        let factory = StreamFactory::with_stream_bits(root.clone(), 4);
+        assert_eq!(factory.stream(0), root);
+        let mut sequential = root;
+        for index in 0..8 {
+            assert_eq!(factory.stream(index), sequential);
+            for _ in 0..factory.max_draws_per_stream() {
+                sequential.next_u64();
+            }
+        }
+    }
+}
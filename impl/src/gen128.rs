@@ -0,0 +1,101 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use rand_core::{le, SeedableRng};
+
+use crate::mwc::Mwc;
+use crate::seed::Seed512;
+
+// `MULTIPLIER * 2^384 - 1` is a safe prime, found with `gen_64_bit_candidates_for_shift` in `parm_search.rs`.
+pub(crate) const MULTIPLIER: u64 = 0xffbb_4292_740b_cf7d;
+
+/// A PCG random number generator (MWC X A 512/128 variant).
+///
+/// The same design as `Mwc256XXA64` with 128-bit words and a 64-bit multiplier, so each step is a 128x64 bit
+/// multiply (two 64 bit multiplies on 64 bit hosts). Use `next_u128` or `fill_u128` to get whole outputs,
+/// the `RngCore` methods truncate them.
+///
+/// The period is about `2^447` (half of `MULTIPLIER * 2^384`). Despite the name, which counts three 128-bit words and
+/// a 128-bit carry, the carry is always less than the 64-bit multiplier, so only 448 bits of the state are effective.
+///
+/// Because the multiplier is only 64 bits, the high half of the product which is xored into the newest word is
+/// also only 64 bits, so the upper half of each output is mixed only by the carries of the addition.
+pub type Mwc512XXA128 = Mwc<u128, 3, MULTIPLIER>;
+
+impl Mwc512XXA128 {
+    /// Returns the next output.
+    #[inline]
+    pub fn next_u128(&mut self) -> u128 {
+        self.step()
+    }
+}
+
+/// We use a single 441-bit seed to initialise the state and select a stream.
+/// The upper 64 bits of the first `seed` word are ignored, because the carry is less than the 64 bit multiplier.
+impl SeedableRng for Mwc512XXA128 {
+    type Seed = Seed512;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_u64 = [0u64; 8];
+        le::read_u64_into(&seed.0, &mut seed_u64);
+        let word = |i: usize| seed_u64[2 * i] as u128 | (seed_u64[2 * i + 1] as u128) << 64;
+        // c must be < MULTIPLE and not all 1s or 0s
+        let c = (seed_u64[0] & 0x3fff_ffff_ffff_fff8) | 5;
+        // X3 must be non-zero and not all 1s, hence we discard 2 bits
+        let x3 = (word(3) << 2) | 1;
+        Mwc512XXA128::from_state_incr([word(1), word(2), x3], c as u128)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    use crate::mwc::test::check_seeded_states_valid;
+
+    #[test]
+    fn test_seeded_states_are_valid() {
+        check_seeded_states_valid::<Mwc512XXA128>();
+    }
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(format!("{:?}", Mwc512XXA128::seed_from_u64(1)), "Mwc512XXA128 {}");
+    }
+
+    #[test]
+    fn test_step() {
+        let mut rng = Mwc512XXA128::seed_from_u64(2);
+        for _ in 0..1000 {
+            let ([x1, x2, x3], c) = rng.to_words();
+            // Compute `MULTIPLIER * x3` in 64 bit limbs.
+            let low = (x3 as u64 as u128) * MULTIPLIER as u128;
+            let high = (x3 >> 64) * MULTIPLIER as u128 + (low >> 64);
+            let (product, hi) = (low as u64 as u128 | high << 64, high >> 64);
+            let (x1_next, carry) = product.overflowing_add(c);
+            assert_eq!(rng.next_u128(), (x3 ^ x2).wrapping_add(x1 ^ hi));
+            assert_eq!(rng.to_words(), ([x1_next, x1, x2], hi + carry as u128));
+        }
+    }
+
+    #[test]
+    fn test_fills_agree() {
+        let rng = Mwc512XXA128::seed_from_u64(3);
+        let mut words = [0u128; 20];
+        rng.clone().fill_u128(&mut words);
+        let mut bytes = [0u8; 20 * 16];
+        rng.clone().fill_bytes(&mut bytes);
+        let mut halves = [0u64; 40];
+        rng.clone().fill_u64(&mut halves);
+        let mut stepped = rng;
+        for (i, &word) in words.iter().enumerate() {
+            assert_eq!(stepped.next_u128(), word);
+            assert_eq!(&bytes[16 * i..16 * (i + 1)], &word.to_le_bytes()[..]);
+            assert_eq!([halves[2 * i], halves[2 * i + 1]], [word as u64, (word >> 64) as u64]);
+        }
+    }
+}
@@ -1,100 +1,60 @@
-use rand_core::{RngCore, Error, SeedableRng, le};
+use rand_core::{SeedableRng, le};
 
-#[cfg(feature = "serde1")]
-use serde::{Deserialize, Serialize};
+use crate::mwc::{Mwc, MwcWord};
 
 // Deliberately poor constants for testing:
 // 2562598503 - Lag-2 or 3 Truly awful spectra
 // 2487410280 - Lag-2 or 3 Very bad spectra
 
-const MULTIPLIER: u32 = 3487286589; //Suitable for lag-2,3,4 acceptably good spectra
+pub(crate) const MULTIPLIER: u32 = 3487286589; //Suitable for lag-2,3,4 acceptably good spectra
 
 /// A PCG random number generator (MWC X A 128/32 variant).
 ///
 /// Permuted Congruential Generator with 128-bit state, internal multiply
 /// with carry Generator, and 32-bit output via a xor and an add.
-#[derive(Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
-pub struct Mwc128XXA32 {
-    pub(crate) x1: u32,
-    pub(crate) x2: u32,
-    pub(crate) x3: u32,
-    pub(crate) c: u32,
-}
+pub type Mwc128XXA32 = Mwc<u32, 3, { MULTIPLIER as u64 }>;
 
 impl Mwc128XXA32 {
     /// Construct an instance given two keys.
     pub fn new(k1: u32, k2: u32) -> Self {
         // X3 is 0xcafef00d 0xd15ea5e5 (default state from PCG paper because it cannot be 0.
         // C must be initialized to a value > 1 and < MULTIPLIER
-        Mwc128XXA32::from_state_incr(k1, k2, 0xcafef00d, 0xd15ea5e5)
+        Mwc128XXA32::from_state_incr([k1, k2, 0xcafef00d], 0xd15ea5e5)
     }
 
-    #[inline]
-    fn from_state_incr(x1: u32, x2: u32, x3: u32, c: u32) -> Self {
-        let mut pcg = Mwc128XXA32 { x1, x2, x3, c };
-        //Advance 6 steps to fully mix the keys.
-        pcg.gen6();
-        pcg
+    /// Construct an instance given a seed and a stream id.
+    ///
+    /// The seed provides `x1` and `x2` and the stream provides `x3`, with a fixed carry of `0xcafef00d`.
+    ///
+    /// The modulus `MULTIPLIER * 2^96 - 1` of the equivalent MCG is a safe prime `2p + 1`, so apart from the two
+    /// fixed points the states form two cycles of length `p = MULTIPLIER * 2^95 - 1`, about `2^126`. Every seed and
+    /// stream selects a distinct starting point on one of them. All `2^32` streams of a seed are distinct, and
+    /// because the starting points are effectively random, the probability that any two of `k` streams which each
+    /// produce `n` outputs overlap is at most about `k^2 * n / 2^126`. (For `2^32` streams of `2^48` outputs each
+    /// this is `2^-14`.) If overlap must be ruled out entirely, derive the streams from one root state with
+    /// `advance`: they stay on the cycle of the root, so they cannot overlap within the distance they were advanced
+    /// apart.
+    pub fn with_stream(seed: u64, stream: u32) -> Self {
+        // Unlike the constant used by `new` this carry is < MULTIPLIER, so the state is always canonical.
+        Mwc128XXA32::from_state_incr([seed as u32, (seed >> 32) as u32, stream], 0xcafef00d)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> u32 {
         self.step()
     }
 
+    /// Returns the next two outputs combined into a `u64`, with the first output in the low half.
+    ///
+    /// This is the same order as `fill_bytes` and `fill_u64` use, whereas `RngCore::next_u64` puts the first
+    /// output in the high half (which is kept for compatibility with existing seeds).
     #[inline]
-    fn step(&mut self) -> u32 {
-        // prepare the MCG for the next round
-        let (low, hi) = multiply(self.x3);
-        let result = permute(self.x1, self.x2, self.x3, self.c, low, hi);
-        let (x1, b) = low.overflowing_add(self.c);
-        self.x3 = self.x2;
-        self.x2 = self.x1;
-        self.x1 = x1;
-        self.c = hi.wrapping_add(b as u32);
-        result
-    }
-
-    #[inline]
-    fn gen6(&mut self) -> [u32; 6] {
-        //This is faster than calling `next_u32` 6 times because it avoids the intermediate assignments to the member variables.
-        //For some reason the compiler doesn't figure this out automatically.
-        let mut result = [0; 6];
-        let (low, hi) = multiply(self.x3);
-        result[0] = permute(self.x1, self.x2, self.x3, self.c, low, hi);
-        let (r1, b) = low.overflowing_add(self.c);
-        let c = hi.wrapping_add(b as u32);
-        let (low, hi) = multiply(self.x2);
-        result[1] = permute(r1, self.x1, self.x2, c, low, hi);
-        let (r2, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u32);
-        let (low, hi) = multiply(self.x1);
-        result[2] = permute(r2, r1, self.x1, c, low, hi);
-        let (r3, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u32);
-
-        let (low, hi) = multiply(r1);
-        result[3] = permute(r3, r2, r1, c, low, hi);
-        let (r1, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u32);
-        let (low, hi) = multiply(r2);
-        result[4] = permute(r1, r3, r2, c, low, hi);
-        let (r2, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u32);
-        let (low, hi) = multiply(r3);
-        result[5] = permute(r2, r1, r3, c, low, hi);
-        let (r3, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u32);
-
-        self.c = c;
-        self.x1 = r3;
-        self.x2 = r2;
-        self.x3 = r1;
-        return result;
+    pub fn next_u64_le(&mut self) -> u64 {
+        let [first, second] = self.gen_array();
+        (first as u64) | (second as u64) << 32
     }
 }
 
-
 /// We use a single 121-bit seed to initialise the state and select a stream.
 /// Of the 128 `seed` bits 7 are ignored.
 impl SeedableRng for Mwc128XXA32 {
@@ -107,51 +67,78 @@ impl SeedableRng for Mwc128XXA32 {
         let c = (seed_u32[0] & 0x3fff_fff8) | 5;
         // X3 must be non-zero and not all 1s, hence we discard 2 bits
         let x3 = (seed_u32[3] << 2) | 1;
-        Mwc128XXA32::from_state_incr(seed_u32[1], seed_u32[2], x3, c)
+        Mwc128XXA32::from_state_incr([seed_u32[1], seed_u32[2], x3], c)
     }
 }
 
-impl RngCore for Mwc128XXA32 {
-    #[inline]
-    fn next_u32(&mut self) -> u32 {
-        self.step() as u32
+#[inline(always)]
+pub(crate) fn multiply(val: u32) -> (u32, u32) {
+    val.mul_wide(MULTIPLIER as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    use crate::mwc::test::{
+        check_advance_composes, check_advance_matches_step, check_outputs_differ, check_prev_reverses_next,
+        check_split, check_state_round_trip,
+    };
+
+    #[test]
+    fn test_with_stream() {
+        check_outputs_differ(Mwc128XXA32::with_stream(1, 0), Mwc128XXA32::with_stream(1, 1));
+        check_outputs_differ(Mwc128XXA32::with_stream(1, 0), Mwc128XXA32::with_stream(2, 0));
     }
 
-    #[inline]
-    fn next_u64(&mut self) -> u64 {
-        let result = self.step() as u64;
-        return (result << 32) | (self.step() as u64);
+    #[test]
+    fn test_split() {
+        check_split(Mwc128XXA32::seed_from_u64(11));
     }
 
-    #[inline]
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        let mut dest_chunks = dest.chunks_exact_mut(6 * 4);
-        for mut dest_chunk in &mut dest_chunks {
-            for &num in self.gen6().iter() {
-                let (l, r) = dest_chunk.split_at_mut(4);
-                l.copy_from_slice(&num.to_le_bytes());
-                dest_chunk = r;
-            }
+    #[test]
+    fn test_state_round_trip() {
+        check_state_round_trip(Mwc128XXA32::seed_from_u64(5));
+    }
+
+    #[test]
+    fn test_prev_reverses_next() {
+        check_prev_reverses_next(Mwc128XXA32::new(3, 4));
+    }
+
+    #[test]
+    fn test_advance_matches_step() {
+        check_advance_matches_step(Mwc128XXA32::new(1, 2));
+        check_advance_composes(Mwc128XXA32::seed_from_u64(3));
+    }
+
+    #[test]
+    fn test_next_u64_word_order() {
+        let mut rng = Mwc128XXA32::seed_from_u64(20);
+        let mut expected = rng.clone();
+        for _ in 0..10 {
+            let (first, second) = (expected.step() as u64, expected.step() as u64);
+            assert_eq!(rng.next_u64(), first << 32 | second);
         }
-        for dest_chunk in dest_chunks.into_remainder().chunks_mut(4) {
-            dest_chunk.copy_from_slice(&self.step().to_le_bytes()[..dest_chunk.len()]);
+        let mut words = [0u64; 10];
+        expected.fill_u64(&mut words);
+        for &word in words.iter() {
+            assert_eq!(rng.next_u64_le(), word);
         }
+        assert_eq!(rng, expected);
     }
 
-    #[inline(always)]
-    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        self.fill_bytes(dest);
-        Ok(())
+    #[test]
+    fn test_advance_full_period() {
+        // The modulus is a safe prime 2p + 1 and 2^32 is a square, so the period is exactly p.
+        let period = ((MULTIPLIER as u128) << 95) - 1;
+        let mut rng = Mwc128XXA32::seed_from_u64(7);
+        let start = rng.clone();
+        rng.advance(period);
+        assert_eq!(rng, start);
+        rng.advance(period - 1);
+        rng.step();
+        assert_eq!(rng, start);
     }
 }
-
-#[inline(always)]
-fn multiply(val: u32) -> (u32, u32) {
-    let t = (val as u64).wrapping_mul(MULTIPLIER as u64);
-    return (t as u32, (t >> 32) as u32);
-}
-
-#[inline(always)]
-fn permute(x1: u32, x2: u32, x3: u32, _c: u32, _low: u32, hi: u32) -> u32 {
-    (x3 ^ x2).wrapping_add(x1 ^ hi)
-}
\ No newline at end of file
@@ -4,114 +4,105 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use core::fmt;
-use rand_core::{Error, le, RngCore, SeedableRng};
+use rand_core::{le, SeedableRng};
+#[cfg(feature = "rayon")]
+use rand_core::RngCore;
 
-#[cfg(feature = "serde1")]
-use serde::{Deserialize, Serialize};
+use crate::mwc::{Mwc, MwcWord};
+use crate::SeedSequence;
 
 // This is the default multiplier used by MWC.
-const MULTIPLIER: u64 = 0xfeb3_4465_7c0a_f413; //Best spectra for lag 3
+pub(crate) const MULTIPLIER: u64 = 0xfeb3_4465_7c0a_f413; //Best spectra for lag 3
 // For testing with a lag of 1, 3, or 4  the following work: 0x7c49_2513_927a_59b3 or 0xa729_8353_f425_0d13
+// (The latter is used by the lag-4 `Mwc320XXA64`.)
 
 /// A PCG random number generator (MWC X A 256/64 variant).
 ///
 /// Permuted Congruential Generator with 256-bit state, internal multiply
 /// with carry Generator, and 64-bit output via a xor and an add.
-#[derive(Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde1", derive(Serialize, Deserialize))]
-pub struct Mwc256XXA64 {
-    x1: u64,
-    x2: u64,
-    x3: u64,
-    c: u64,
-}
+pub type Mwc256XXA64 = Mwc<u64, 3, MULTIPLIER>;
 
 impl Mwc256XXA64 {
     /// Construct an instance given two keys.
     pub fn new(k1: u64, k2: u64) -> Self {
         // X3 is 0xcafef00dd15ea5e5 (default state from PCG paper because it cannot be 0.
         // C must be initialized to a value > 1 and < MULTIPLIER
-        Mwc256XXA64::from_state_incr(k1, k2, 0xcafef00dd15ea5e5, 0x14057B7EF767814F)
+        Mwc256XXA64::from_state_incr([k1, k2, 0xcafef00dd15ea5e5], 0x14057B7EF767814F)
     }
 
-    #[inline]
-    fn from_state_incr(x1: u64, x2: u64, x3: u64, c: u64) -> Self {
-        let mut pcg = Mwc256XXA64 { x1, x2, x3, c };
-        //Advance 6 steps to fully mix the keys.
-        pcg.gen6();
-        pcg
+    /// Construct an instance given a seed and a stream id.
+    ///
+    /// The seed provides `x1` and `x2` and the stream provides `x3`, with the same carry as `new`. So
+    /// `new(k1, k2)` is the stream `0xcafef00dd15ea5e5` of the seed `(k2 << 64) | k1`.
+    ///
+    /// The modulus `MULTIPLIER * 2^192 - 1` of the equivalent MCG is a safe prime `2p + 1`, so apart from the two
+    /// fixed points the states form two cycles of length `p = MULTIPLIER * 2^191 - 1`, a little under `2^255`. Every
+    /// seed and stream selects a distinct starting point on one of them. All `2^64` streams of a seed are distinct,
+    /// and because the starting points are effectively random, the probability that any two of `k` streams which
+    /// each produce `n` outputs overlap is at most about `k^2 * n / 2^255`. (For `2^64` streams of `2^64` outputs
+    /// each this is `2^-63`.) If overlap must be ruled out entirely, derive the streams from one root state with
+    /// `advance` (as `StreamFactory` does): they stay on the cycle of the root, so they cannot overlap within the
+    /// distance they were advanced apart.
+    pub fn with_stream(seed: u128, stream: u64) -> Self {
+        Mwc256XXA64::from_state_incr([seed as u64, (seed >> 64) as u64, stream], 0x14057B7EF767814F)
     }
 
-    #[inline]
-    fn gen6(&mut self) -> [u64; 6] {
-        //This is faster than calling `next_u64` 6 times because it avoids the intermediate assignments to the member variables.
-        //For some reason the compiler doesn't figure this out automatically.
-        let mut result = [0; 6];
-        let (low, hi) = multiply(self.x3);
-        result[0] = permute(self.x1, self.x2, self.x3, self.c, low, hi);
-        let (r1, b) = low.overflowing_add(self.c);
-        let c = hi.wrapping_add(b as u64);
-        let (low, hi) = multiply(self.x2);
-        result[1] = permute(r1, self.x1, self.x2, c, low, hi);
-        let (r2, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u64);
-        let (low, hi) = multiply(self.x1);
-        result[2] = permute(r2, r1, self.x1, c, low, hi);
-        let (r3, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u64);
-
-        let (low, hi) = multiply(r1);
-        result[3] = permute(r3, r2, r1, c, low, hi);
-        let (r1, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u64);
-        let (low, hi) = multiply(r2);
-        result[4] = permute(r1, r3, r2, c, low, hi);
-        let (r2, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u64);
-        let (low, hi) = multiply(r3);
-        result[5] = permute(r2, r1, r3, c, low, hi);
-        let (r3, b) = low.overflowing_add(c);
-        let c = hi.wrapping_add(b as u64);
-
-        self.c = c;
-        self.x1 = r3;
-        self.x2 = r2;
-        self.x3 = r1;
-        return result;
+    /// Derives an independent generator for the given label, without modifying this one.
+    ///
+    /// The state and the label are hashed together by a `SeedSequence`, so the result only depends on the
+    /// two of them and not on how many other generators were derived before. Different labels give unrelated
+    /// generators, and derivations can be nested, for example `rng.derive("physics").derive("particles")`.
+    pub fn derive(&self, label: &str) -> Self {
+        let mut entropy: Vec<u8> = self.to_state().iter().flat_map(|word| word.to_le_bytes()).collect();
+        entropy.extend_from_slice(label.as_bytes());
+        Mwc256XXA64::from_seed_sequence(&SeedSequence::from_bytes(&entropy))
     }
 
-    #[inline]
-    fn step(&mut self) -> u64 {
-        // prepare the MCG for the next round
-        let (low, hi) = multiply(self.x3);
-        let result = permute(self.x1, self.x2, self.x3, self.c, low, hi);
-        let (x1, b) = low.overflowing_add(self.c);
-        self.x3 = self.x2;
-        self.x2 = self.x1;
-        self.x1 = x1;
-        self.c = hi.wrapping_add(b as u64);
-        result
+    /// Returns the output at position `index` (counting from zero) of the sequence produced by repeated
+    /// calls to `next_u64`, without modifying the generator. This takes `O(log(index))` time.
+    pub fn nth_u64(&self, index: u128) -> u64 {
+        let mut rng = self.clone();
+        rng.advance(index);
+        rng.step()
     }
-}
 
-#[inline(always)]
-fn multiply(val: u64) -> (u64, u64) {
-    //While this looks like 128 bit math, it compiles to a 64 bit multiply.
-    let t = (val as u128).wrapping_mul(MULTIPLIER as u128);
-    return (t as u64, (t >> 64) as u64);
-}
+    /// Returns the output at position `index` of the generator created by `from_seed(seed)`.
+    ///
+    /// This allows independent workers to compute any element of a reproducible sequence directly.
+    pub fn output_at(seed: [u8; 32], index: u128) -> u64 {
+        Mwc256XXA64::from_seed(seed).nth_u64(index)
+    }
 
-#[inline(always)]
-fn permute(x1: u64, x2: u64, x3: u64, _c: u64, _low: u64, hi: u64) -> u64 {
-    (x3 ^ x2).wrapping_add(x1 ^ hi)
+    /// Fills `dest` in parallel using rayon. The output is identical to `fill_bytes` and the generator
+    /// is left in the same state, regardless of the number of threads.
+    ///
+    /// The buffer is split into chunks of `PAR_CHUNK_BYTES`, and each is filled by a copy of the generator
+    /// moved to that chunk's offset with `advance`.
+    #[cfg(feature = "rayon")]
+    pub fn par_fill_bytes(&mut self, dest: &mut [u8]) {
+        use rayon::prelude::*;
+        if dest.len() <= PAR_CHUNK_BYTES {
+            self.fill_bytes(dest);
+            return;
+        }
+        let start = self.clone();
+        dest.par_chunks_mut(PAR_CHUNK_BYTES).enumerate().for_each(|(i, chunk)| {
+            let mut rng = start.clone();
+            rng.advance((i * (PAR_CHUNK_BYTES / 8)) as u128);
+            rng.fill_bytes(chunk);
+        });
+        //`fill_bytes` uses one output per 8 bytes, including a partial final word.
+        self.advance(dest.len().div_ceil(8) as u128);
+    }
 }
 
-// Custom Debug implementation that does not expose the internal state
-impl fmt::Debug for Mwc256XXA64 {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Mwc256XXA64 {{}}")
-    }
+// Bytes filled by each task of `par_fill_bytes`. This must be a multiple of 8.
+#[cfg(feature = "rayon")]
+const PAR_CHUNK_BYTES: usize = 1 << 20;
+
+#[inline(always)]
+pub(crate) fn multiply(val: u64) -> (u64, u64) {
+    val.mul_wide(MULTIPLIER)
 }
 
 /// We use a single 249-bit seed to initialise the state and select a stream.
@@ -126,40 +117,94 @@ impl SeedableRng for Mwc256XXA64 {
         let c = (seed_u64[0] & 0x3ffffffffffffff8) | 5;
         // X3 must be non-zero and not all 1s, hence we discard 2 bits
         let x3 = (seed_u64[3] << 2) | 1;
-        Mwc256XXA64::from_state_incr(seed_u64[1], seed_u64[2], x3, c)
+        Mwc256XXA64::from_state_incr([seed_u64[1], seed_u64[2], x3], c)
     }
 }
 
-impl RngCore for Mwc256XXA64 {
-    #[inline]
-    fn next_u32(&mut self) -> u32 {
-        self.next_u64() as u32
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    use crate::mwc::test::{
+        check_advance_composes, check_advance_matches_step, check_outputs_differ, check_prev_reverses_next,
+        check_split, check_state_round_trip, matching_bit_fraction,
+    };
+
+    #[test]
+    fn test_with_stream() {
+        assert_eq!(Mwc256XXA64::with_stream(2 << 64 | 1, 0xcafef00dd15ea5e5), Mwc256XXA64::new(1, 2));
+        check_outputs_differ(Mwc256XXA64::with_stream(1, 0), Mwc256XXA64::with_stream(1, 1));
+        check_outputs_differ(Mwc256XXA64::with_stream(1, 0), Mwc256XXA64::with_stream(2, 0));
     }
 
-    #[inline]
-    fn next_u64(&mut self) -> u64 {
-        self.step()
+    #[test]
+    fn test_split() {
+        check_split(Mwc256XXA64::seed_from_u64(11));
     }
 
-    #[inline]
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        let mut dest_chunks = dest.chunks_exact_mut(6 * 8);
-        for mut dest_chunk in &mut dest_chunks {
-            for &num in self.gen6().iter() {
-                let (l, r) = dest_chunk.split_at_mut(8);
-                l.copy_from_slice(&num.to_le_bytes());
-                dest_chunk = r;
-            }
-        }
-        for dest_chunk in dest_chunks.into_remainder().chunks_mut(8) {
-            dest_chunk.copy_from_slice(&self.step().to_le_bytes()[..dest_chunk.len()]);
+    #[test]
+    fn test_state_round_trip() {
+        check_state_round_trip(Mwc256XXA64::seed_from_u64(5));
+    }
+
+    #[test]
+    fn test_prev_reverses_next() {
+        check_prev_reverses_next(Mwc256XXA64::new(3, 4));
+    }
+
+    #[test]
+    fn test_advance_matches_step() {
+        check_advance_matches_step(Mwc256XXA64::new(1, 2));
+        check_advance_composes(Mwc256XXA64::seed_from_u64(3));
+    }
+
+    #[test]
+    fn test_derive() {
+        let parent = Mwc256XXA64::seed_from_u64(12);
+        let physics = parent.derive("physics");
+        let ai = parent.derive("ai");
+        assert_eq!(parent, Mwc256XXA64::seed_from_u64(12));
+        assert_eq!(physics, Mwc256XXA64::seed_from_u64(12).derive("physics"));
+        assert_ne!(parent.derive("a"), parent.derive("a\0"));
+        let particles = physics.derive("particles");
+        assert_ne!(particles, physics.derive("planner"));
+        assert_ne!(particles, Mwc256XXA64::seed_from_u64(13).derive("physics").derive("particles"));
+        assert!((matching_bit_fraction(&mut physics.clone(), &mut ai.clone()) - 0.5).abs() < 0.01);
+        assert!((matching_bit_fraction(&mut parent.clone(), &mut physics.clone()) - 0.5).abs() < 0.01);
+        assert!((matching_bit_fraction(&mut physics.clone(), &mut particles.clone()) - 0.5).abs() < 0.01);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_fill_bytes_matches_fill_bytes() {
+        for &len in &[0, 13, PAR_CHUNK_BYTES, PAR_CHUNK_BYTES + 1, 3 * PAR_CHUNK_BYTES + 13] {
+            let mut sequential = Mwc256XXA64::new(1, 2);
+            let mut expected = vec![0u8; len];
+            sequential.fill_bytes(&mut expected);
+            let mut parallel = Mwc256XXA64::new(1, 2);
+            let mut actual = vec![0u8; len];
+            parallel.par_fill_bytes(&mut actual);
+            assert!(expected == actual);
+            assert_eq!(sequential, parallel);
         }
     }
 
-    #[inline(always)]
-    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        self.fill_bytes(dest);
-        Ok(())
+    #[test]
+    fn test_nth_u64() {
+        let seed = [7; 32];
+        let mut rng = Mwc256XXA64::from_seed(seed);
+        let start = rng.clone();
+        for index in 0..100 {
+            let expected = rng.next_u64();
+            assert_eq!(start.nth_u64(index), expected);
+            assert_eq!(Mwc256XXA64::output_at(seed, index), expected);
+        }
+        let index = 1 << 100;
+        let mut far = start.clone();
+        far.advance(index);
+        assert_eq!(start.nth_u64(index), far.next_u64());
+        assert_eq!(start.nth_u64(index + 1), far.next_u64());
     }
-}
 
+}
@@ -0,0 +1,129 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Siblings of `Mwc256XXA64` with a smaller (lag-2) or larger (lag-4 and lag-15) state.
+
+use rand_core::{le, SeedableRng};
+
+use crate::mwc::Mwc;
+use crate::seed::Seed1024;
+
+// `MULTIPLIER * 2^128 - 1` is a safe prime. None of the lag-3 or lag-4 multipliers work for lag 2, so this was
+// found with the filters of `gen_64_bit_candidates` in `parm_search.rs`.
+pub(crate) const MULTIPLIER_LAG2: u64 = 0xfff0_2b36_e685_963b;
+// `MULTIPLIER * 2^256 - 1` is a safe prime. (This also holds for lag 1 and 3.) It is one of the values `gen64.rs`
+// lists for testing, and its spectra have not been checked.
+pub(crate) const MULTIPLIER_LAG4: u64 = 0xa729_8353_f425_0d13;
+// `MULTIPLIER * 2^960 - 1` is a safe prime, found with the same filters as `MULTIPLIER_LAG2`.
+pub(crate) const MULTIPLIER_LAG15: u64 = 0xffe5_4e9b_5582_01ab;
+
+/// A PCG random number generator (MWC X A 192/64 variant).
+///
+/// The same design as `Mwc256XXA64` with one less word of state, for when memory is tight. The period is about
+/// `2^191`, which is still far more than can ever be used, but with only two words the output permutation
+/// `(x2 ^ x1) + (x1 ^ hi)` mixes fewer independent values, so prefer `Mwc256XXA64` unless the size matters.
+pub type Mwc192XXA64 = Mwc<u64, 2, MULTIPLIER_LAG2>;
+
+/// A PCG random number generator (MWC X A 320/64 variant).
+///
+/// The same design as `Mwc256XXA64` with one more word of state, for a period of about `2^318`. Its multiplier has
+/// not been checked for good spectra (see `Mwc`), so prefer `Mwc256XXA64` unless the longer period is needed.
+///
+/// `next_u64` takes about a tenth longer than with `Mwc256XXA64`, and the fill methods are as fast. (See
+/// `benches/bench.rs`.)
+pub type Mwc320XXA64 = Mwc<u64, 4, MULTIPLIER_LAG4>;
+
+/// A PCG random number generator (MWC X A 1024/64 variant).
+///
+/// The same design as `Mwc256XXA64` with 15 words of state, for high dimensional simulations. The period is
+/// about `2^1023`, and the output `(x15 ^ x14) + (x1 ^ hi)` is the same permutation applied to the oldest and newest
+/// words. Like `Mwc256XXA64` it supports jumping ahead with `advance`.
+///
+/// The larger state cannot be kept in registers, and every step shifts all 15 words through memory, so
+/// `next_u64` takes about four times as long as with `Mwc256XXA64`. The fill methods generate several outputs per
+/// pass and take only about a third longer, so prefer them for bulk output. (See `benches/bench.rs`.)
+pub type Mwc1024XXA64 = Mwc<u64, 15, MULTIPLIER_LAG15>;
+
+/// We use a single 185-bit seed to initialise the state and select a stream.
+impl SeedableRng for Mwc192XXA64 {
+    type Seed = [u8; 24];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_u64 = [0u64; 3];
+        le::read_u64_into(&seed, &mut seed_u64);
+        // c must be < MULTIPLE and not all 1s or 0s
+        let c = (seed_u64[0] & 0x3fff_ffff_ffff_fff8) | 5;
+        // X2 must be non-zero and not all 1s, hence we discard 2 bits
+        let x2 = (seed_u64[2] << 2) | 1;
+        Mwc192XXA64::from_state_incr([seed_u64[1], x2], c)
+    }
+}
+
+/// We use a single 251-bit seed to initialise the state and select a stream. The oldest word starts as a constant.
+impl SeedableRng for Mwc320XXA64 {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_u64 = [0u64; 4];
+        le::read_u64_into(&seed, &mut seed_u64);
+        // c must be < MULTIPLE and not all 1s or 0s
+        let c = (seed_u64[0] & 0x3fff_ffff_ffff_fff8) | 5;
+        // X4 is 0xcafef00dd15ea5e5 (default state from PCG paper) because it cannot be 0.
+        Mwc320XXA64::from_state_incr([seed_u64[1], seed_u64[2], seed_u64[3], 0xcafef00dd15ea5e5], c)
+    }
+}
+
+/// We use a single 1017-bit seed to initialise the state and select a stream.
+impl SeedableRng for Mwc1024XXA64 {
+    type Seed = Seed1024;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_u64 = [0u64; 16];
+        le::read_u64_into(&seed.0, &mut seed_u64);
+        // c must be < MULTIPLE and not all 1s or 0s
+        let c = (seed_u64[0] & 0x3fff_ffff_ffff_fff8) | 5;
+        let mut x = [0; 15];
+        x.copy_from_slice(&seed_u64[1..]);
+        // X15 must be non-zero and not all 1s, hence we discard 2 bits
+        x[14] = (x[14] << 2) | 1;
+        Mwc1024XXA64::from_state_incr(x, c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    use crate::mwc::test::{check_advance_composes, check_advance_matches_step, check_seeded_states_valid};
+
+    #[test]
+    fn test_seeded_states_are_valid() {
+        check_seeded_states_valid::<Mwc192XXA64>();
+        check_seeded_states_valid::<Mwc320XXA64>();
+        check_seeded_states_valid::<Mwc1024XXA64>();
+    }
+
+    #[test]
+    fn test_lag15_advance_matches_step() {
+        check_advance_matches_step(Mwc1024XXA64::seed_from_u64(1));
+        check_advance_composes(Mwc1024XXA64::seed_from_u64(3));
+    }
+
+    #[test]
+    fn test_outputs() {
+        let mut a = Mwc192XXA64::seed_from_u64(1);
+        let mut b = Mwc192XXA64::seed_from_u64(2);
+        let mut c = Mwc320XXA64::seed_from_u64(1);
+        let mut d = Mwc320XXA64::seed_from_u64(2);
+        for _ in 0..10 {
+            assert_ne!(a.next_u64(), b.next_u64());
+            assert_ne!(c.next_u64(), d.next_u64());
+        }
+        assert_eq!(format!("{:?} {:?}", a, c), "Mwc192XXA64 {} Mwc320XXA64 {}");
+        assert_eq!(format!("{:?}", Mwc1024XXA64::seed_from_u64(1)), "Mwc1024XXA64 {}");
+    }
+}
@@ -0,0 +1,79 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The fill methods shared by the generators which run several lanes in lockstep.
+
+use core::slice;
+
+/// A type the output of a lane can be written as: the word itself, or its little endian bytes.
+///
+/// Both have the size of the word, and the same layout on little endian targets, so SIMD code can store whole
+/// registers into either.
+pub(crate) trait LaneValue<W>: Copy {
+    fn from_word(word: W) -> Self;
+}
+
+macro_rules! impl_lane_value {
+    ($word:ty, $bytes:literal) => {
+        impl LaneValue<$word> for $word {
+            #[inline(always)]
+            fn from_word(word: $word) -> Self {
+                word
+            }
+        }
+
+        impl LaneValue<$word> for [u8; $bytes] {
+            #[inline(always)]
+            fn from_word(word: $word) -> Self {
+                word.to_le_bytes()
+            }
+        }
+    };
+}
+
+impl_lane_value!(u32, 4);
+impl_lane_value!(u64, 8);
+
+/// A generator with `LANES` lanes, each step of which produces a row of one value from every lane.
+pub(crate) trait LaneRows<W: Copy + Default, const LANES: usize> {
+    /// Fills `rows`, whose length must be a multiple of `LANES`.
+    fn fill_rows<T: LaneValue<W>>(&mut self, rows: &mut [T]);
+
+    /// Fills `dest` with whole rows, taking the last values from one more row when its length is not a multiple of
+    /// `LANES`.
+    #[inline]
+    fn fill_values<T: LaneValue<W>>(&mut self, dest: &mut [T]) {
+        let full = dest.len() - dest.len() % LANES;
+        let (rows, remainder) = dest.split_at_mut(full);
+        self.fill_rows(rows);
+        if !remainder.is_empty() {
+            let mut values = [T::from_word(W::default()); LANES];
+            self.fill_rows(&mut values);
+            remainder.copy_from_slice(&values[..remainder.len()]);
+        }
+    }
+
+    /// Fills `dest` with the rows as little endian bytes. Whole rows are written directly into `dest`, only a final
+    /// partial row goes through a buffer.
+    #[inline]
+    fn fill_le_bytes<const BYTES: usize>(&mut self, dest: &mut [u8])
+    where
+        [u8; BYTES]: LaneValue<W>,
+    {
+        let full = dest.len() / (BYTES * LANES) * LANES;
+        let (rows, remainder) = dest.split_at_mut(full * BYTES);
+        // Safety: `[u8; BYTES]` has no alignment requirement and `rows` is exactly `full` of them long.
+        let rows = unsafe { slice::from_raw_parts_mut(rows.as_mut_ptr() as *mut [u8; BYTES], full) };
+        self.fill_rows(rows);
+        if !remainder.is_empty() {
+            let mut values = [[0u8; BYTES]; LANES];
+            self.fill_rows(&mut values);
+            for (byte, &value) in remainder.iter_mut().zip(values.iter().flatten()) {
+                *byte = value;
+            }
+        }
+    }
+}
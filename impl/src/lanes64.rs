@@ -0,0 +1,253 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::fmt;
+use rand_core::SeedableRng;
+
+use crate::gen64::{multiply, MULTIPLIER};
+use crate::lanes::{LaneRows, LaneValue};
+use crate::mwc::permute;
+use crate::{Mwc256XXA64, StreamFactory};
+
+// The most groups of four lanes handled by the AVX2 implementation.
+#[cfg(target_arch = "x86_64")]
+const MAX_GROUPS: usize = 2;
+
+/// Four `Mwc256XXA64` generators run in lockstep.
+pub type Mwc256XXA64x4 = Mwc256XXA64Lanes<4>;
+/// Eight `Mwc256XXA64` generators run in lockstep.
+pub type Mwc256XXA64x8 = Mwc256XXA64Lanes<8>;
+
+/// Several independent `Mwc256XXA64` generators (lanes) run in lockstep for bulk throughput.
+///
+/// The carry makes each step of a single generator depend on the previous one. Interleaving independent lanes
+/// stored as a structure of arrays removes that dependency, so the lanes can be stepped in parallel. On x86_64
+/// CPUs with AVX2 (detected at runtime) four lanes are stepped at once in each 256-bit register. Elsewhere a
+/// portable implementation is used, which produces identical output. (Without SIMD the lanes compete for the
+/// same registers, so there the portable implementation is not faster than a single `Mwc256XXA64`.)
+///
+/// Outputs are interleaved: each step produces one value from every lane, lane 0 first. So
+/// `fill_u64` writes `lane0[0], lane1[0], .., lane0[1], lane1[1], ..`. When the length of the destination
+/// is not a multiple of the number of lanes, the unused values of the last step are discarded.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mwc256XXA64Lanes<const LANES: usize> {
+    x1: [u64; LANES],
+    x2: [u64; LANES],
+    x3: [u64; LANES],
+    c: [u64; LANES],
+}
+
+impl<const LANES: usize> Mwc256XXA64Lanes<LANES> {
+    // Evaluated when the generator is constructed, so a generator without lanes fails to compile.
+    const VALID_LANES: () = assert!(LANES > 0, "LANES must be at least 1");
+
+    /// Combines the given generators, which become the lanes in order.
+    pub fn from_lanes(lanes: [Mwc256XXA64; LANES]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_LANES;
+        Mwc256XXA64Lanes {
+            x1: core::array::from_fn(|i| lanes[i].x[0]),
+            x2: core::array::from_fn(|i| lanes[i].x[1]),
+            x3: core::array::from_fn(|i| lanes[i].x[2]),
+            c: core::array::from_fn(|i| lanes[i].c),
+        }
+    }
+
+    /// Splits this into the generators of the individual lanes.
+    pub fn into_lanes(self) -> [Mwc256XXA64; LANES] {
+        core::array::from_fn(|i| Mwc256XXA64 { x: [self.x1[i], self.x2[i], self.x3[i]], c: self.c[i] })
+    }
+
+    /// Returns the next output of every lane.
+    #[inline]
+    pub fn next_lanes(&mut self) -> [u64; LANES] {
+        let mut values = [0; LANES];
+        self.fill_rows_portable(&mut values);
+        values
+    }
+
+    /// Fills `dest` with interleaved outputs of the lanes.
+    #[inline]
+    pub fn fill_u64(&mut self, dest: &mut [u64]) {
+        self.fill_values(dest);
+    }
+
+    /// Fills `dest` with the interleaved outputs of the lanes as little endian bytes.
+    #[inline]
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.fill_le_bytes::<8>(dest);
+    }
+
+    /// Fills `rows`, whose length must be a multiple of `LANES`.
+    #[inline]
+    fn fill_rows_portable<T: LaneValue<u64>>(&mut self, rows: &mut [T]) {
+        // Work on local copies so the state stays in registers for all of the steps.
+        let (mut x1, mut x2, mut x3, mut c) = (self.x1, self.x2, self.x3, self.c);
+        for values in rows.chunks_exact_mut(LANES) {
+            for i in 0..LANES {
+                let (low, hi) = multiply(x3[i]);
+                values[i] = T::from_word(permute(&[x1[i], x2[i], x3[i]], hi));
+                let (r, b) = low.overflowing_add(c[i]);
+                x3[i] = x2[i];
+                x2[i] = x1[i];
+                x1[i] = r;
+                c[i] = hi.wrapping_add(b as u64);
+            }
+        }
+        self.x1 = x1;
+        self.x2 = x2;
+        self.x3 = x3;
+        self.c = c;
+    }
+
+    /// Fills `rows`, whose length must be a multiple of `LANES`, stepping four lanes per 256-bit register.
+    ///
+    /// AVX2 has no 64x64 bit multiply, so the 128-bit product is assembled from four 32x32 bit partial products.
+    /// The results are stored as whole registers, which is the layout of both kinds of `LaneValue<u64>`.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn fill_rows_avx2<T: LaneValue<u64>>(&mut self, rows: &mut [T]) {
+        use core::arch::x86_64::*;
+        let groups = LANES / 4;
+        let a_low = _mm256_set1_epi64x(MULTIPLIER as u32 as i64);
+        let a_high = _mm256_set1_epi64x((MULTIPLIER >> 32) as i64);
+        let low_mask = _mm256_set1_epi64x(u32::MAX as i64);
+        let sign = _mm256_set1_epi64x(i64::MIN);
+        let mut x1 = [_mm256_setzero_si256(); MAX_GROUPS];
+        let mut x2 = x1;
+        let mut x3 = x1;
+        let mut c = x1;
+        for g in 0..groups {
+            x1[g] = _mm256_loadu_si256(self.x1[4 * g..].as_ptr() as *const __m256i);
+            x2[g] = _mm256_loadu_si256(self.x2[4 * g..].as_ptr() as *const __m256i);
+            x3[g] = _mm256_loadu_si256(self.x3[4 * g..].as_ptr() as *const __m256i);
+            c[g] = _mm256_loadu_si256(self.c[4 * g..].as_ptr() as *const __m256i);
+        }
+        for values in rows.chunks_exact_mut(LANES) {
+            for g in 0..groups {
+                let x_high = _mm256_srli_epi64(x3[g], 32);
+                let p0 = _mm256_mul_epu32(x3[g], a_low);
+                let p1 = _mm256_mul_epu32(x3[g], a_high);
+                let p2 = _mm256_mul_epu32(x_high, a_low);
+                let p3 = _mm256_mul_epu32(x_high, a_high);
+                // Each of these sums is less than 2^64, so no carries are lost.
+                let mid = _mm256_add_epi64(
+                    _mm256_add_epi64(p1, _mm256_srli_epi64(p0, 32)),
+                    _mm256_and_si256(p2, low_mask),
+                );
+                let hi = _mm256_add_epi64(
+                    _mm256_add_epi64(p3, _mm256_srli_epi64(mid, 32)),
+                    _mm256_srli_epi64(p2, 32),
+                );
+                let low = _mm256_or_si256(_mm256_slli_epi64(mid, 32), _mm256_and_si256(p0, low_mask));
+                let result = _mm256_add_epi64(_mm256_xor_si256(x3[g], x2[g]), _mm256_xor_si256(x1[g], hi));
+                _mm256_storeu_si256(values[4 * g..].as_mut_ptr() as *mut __m256i, result);
+                let r = _mm256_add_epi64(low, c[g]);
+                // The addition overflowed if r < c. The comparison is signed, so flip the sign bits first.
+                let overflow = _mm256_cmpgt_epi64(_mm256_xor_si256(c[g], sign), _mm256_xor_si256(r, sign));
+                c[g] = _mm256_sub_epi64(hi, overflow);
+                x3[g] = x2[g];
+                x2[g] = x1[g];
+                x1[g] = r;
+            }
+        }
+        for g in 0..groups {
+            _mm256_storeu_si256(self.x1[4 * g..].as_mut_ptr() as *mut __m256i, x1[g]);
+            _mm256_storeu_si256(self.x2[4 * g..].as_mut_ptr() as *mut __m256i, x2[g]);
+            _mm256_storeu_si256(self.x3[4 * g..].as_mut_ptr() as *mut __m256i, x3[g]);
+            _mm256_storeu_si256(self.c[4 * g..].as_mut_ptr() as *mut __m256i, c[g]);
+        }
+    }
+}
+
+impl<const LANES: usize> LaneRows<u64, LANES> for Mwc256XXA64Lanes<LANES> {
+    // `is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    #[inline]
+    fn fill_rows<T: LaneValue<u64>>(&mut self, rows: &mut [T]) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if LANES % 4 == 0 && LANES <= 4 * MAX_GROUPS && is_x86_feature_detected!("avx2") {
+                // Safety: AVX2 is supported by the CPU.
+                unsafe { self.fill_rows_avx2(rows) };
+            } else {
+                self.fill_rows_portable(rows);
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        self.fill_rows_portable(rows);
+    }
+}
+
+// Custom Debug implementation that does not expose the internal state
+impl<const LANES: usize> fmt::Debug for Mwc256XXA64Lanes<LANES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mwc256XXA64Lanes<{}> {{}}", LANES)
+    }
+}
+
+/// The seed initializes a `Mwc256XXA64` and lane `i` is its stream `i` from `StreamFactory::new`,
+/// so the lanes are guaranteed not to overlap for `2^64` steps.
+impl<const LANES: usize> SeedableRng for Mwc256XXA64Lanes<LANES> {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let factory = StreamFactory::new(Mwc256XXA64::from_seed(seed));
+        Mwc256XXA64Lanes::from_lanes(core::array::from_fn(|i| factory.stream(i as u64)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::RngCore;
+
+    fn check_lanes<const LANES: usize>() {
+        let lanes: [Mwc256XXA64; LANES] = core::array::from_fn(|i| Mwc256XXA64::seed_from_u64(i as u64));
+        let mut expected = lanes.clone();
+        let mut interleaved = Mwc256XXA64Lanes::from_lanes(lanes);
+        let mut words = vec![0u64; 20 * LANES + 3];
+        interleaved.fill_u64(&mut words);
+        for row in words.chunks(LANES) {
+            let values: Vec<u64> = expected.iter_mut().map(|rng| rng.next_u64()).collect();
+            assert_eq!(row, &values[..row.len()]);
+        }
+        let mut bytes = vec![0u8; 8 * 14 * LANES - 3];
+        interleaved.fill_bytes(&mut bytes);
+        for row in bytes.chunks(8 * LANES) {
+            let values: Vec<u8> = expected.iter_mut().flat_map(|rng| rng.next_u64().to_le_bytes()).collect();
+            assert_eq!(row, &values[..row.len()]);
+        }
+        assert_eq!(interleaved.into_lanes(), expected);
+    }
+
+    #[test]
+    fn test_matches_scalar_lanes() {
+        check_lanes::<4>();
+        check_lanes::<8>();
+    }
+
+    #[test]
+    fn test_portable_matches_simd() {
+        let mut simd = Mwc256XXA64x8::seed_from_u64(18);
+        let mut portable = simd.clone();
+        let mut simd_words = vec![0u64; 64 * 8];
+        let mut portable_words = vec![0u64; 64 * 8];
+        simd.fill_u64(&mut simd_words);
+        portable.fill_rows_portable(&mut portable_words);
+        assert_eq!(simd_words, portable_words);
+        assert_eq!(simd, portable);
+    }
+
+    #[test]
+    fn test_seeded_lanes_are_streams() {
+        let lanes = Mwc256XXA64x4::seed_from_u64(16).into_lanes();
+        let factory = StreamFactory::new(Mwc256XXA64::seed_from_u64(16));
+        for (i, lane) in lanes.iter().enumerate() {
+            assert_eq!(lane, &factory.stream(i as u64));
+        }
+    }
+}
@@ -4,10 +4,19 @@
 //! This library provides two generators:
 //!
 //! * `Mwc256XXA64` : A Lag-3 64bit MWC generator with two xors and an addition applied to the output.
-//!    It has an output size of 64bits, and a state size of 256bits. This algorithm is fastest on 64 bit architectures.
+//!   It has an output size of 64bits, and a state size of 256bits. This algorithm is fastest on 64 bit architectures.
 //! * `Mwc256XXA64` : A Lag-3 32bit MWC generator with two xors and an addition applied to the output.
-//!    It has an output size of 32bits, and a state size of 128bits. This algorithm is fastest on 32 bit architectures.
+//!   It has an output size of 32bits, and a state size of 128bits. This algorithm is fastest on 32 bit architectures.
 //!
+//! There are also `Mwc192XXA64` and `Mwc320XXA64`, with one word of state less or more than `Mwc256XXA64`, and
+//! `Mwc1024XXA64` with 15 words of state for high dimensional simulations. `Mwc512XXA128` uses 128 bit words, for
+//! consumers of `u128` values.
+//!
+//! `Cmwc256XXA64` and `Cmwc128XXA32` apply the same output permutation to Marsaglia's complementary multiply with
+//! carry generator, for code which was validated against it.
+//!
+//! All of these are instances of the generic `Mwc<W, LAG, MULTIPLIER>` (the complementary ones through the `Cmwc`
+//! alias), which can be used to build variants with other word sizes, lags, or multipliers.
 //!
 //! # Usage
 //!
@@ -15,6 +24,13 @@
 //! [dependencies]
 //! pcg-mwq = "0.2.1"
 //! ```
+//! # Features
+//!
+//! * `serde1` : Serialization support via `serde`. See the `serialization` module for the format.
+//! * `mul32` : Compute the 128 bit products of `Mwc256XXA64` from 32 bit multiplies. This is enabled automatically
+//!   on targets with 32 bit pointers (including `wasm32`), where 128 bit multiplication is a library call.
+//! * `rayon` : Adds `Mwc256XXA64::par_fill_bytes`, which fills large buffers using multiple threads.
+//!
 //! # Typename Nomenclature
 //! The name describes the algorithm.
 //!
@@ -41,9 +57,36 @@
 //! }
 //! ```
 //!
+mod buffered;
+mod cmwc;
+mod factory;
+mod gen128;
 mod gen32;
 mod gen64;
+mod gen64_lags;
+mod lanes;
+mod lanes32;
+mod lanes64;
+mod mcg;
+mod mwc;
+mod seed;
+mod seed_seq;
+#[cfg(feature = "serde1")]
+pub mod serialization;
+mod state;
+mod uninit;
 
+pub use buffered::BufferedMwc256XXA64;
+pub use cmwc::{Cmwc, Cmwc128XXA32, Cmwc256XXA64};
+pub use factory::StreamFactory;
+pub use gen128::Mwc512XXA128;
 pub use gen32::Mwc128XXA32;
 pub use gen64::Mwc256XXA64;
+pub use gen64_lags::{Mwc1024XXA64, Mwc192XXA64, Mwc320XXA64};
+pub use mwc::{Mwc, MwcWord};
+pub use lanes32::Mwc128XXA32x8;
+pub use lanes64::{Mwc256XXA64Lanes, Mwc256XXA64x4, Mwc256XXA64x8};
+pub use seed::{Seed1024, Seed512};
+pub use seed_seq::SeedSequence;
+pub use state::InvalidState;
 
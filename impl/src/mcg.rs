@@ -0,0 +1,211 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Arithmetic on the MCG which is equivalent to a MWC generator.
+//!
+//! A lag-r MWC generator with multiplier `a` and `w` bit words is equivalent to a MCG with the
+//! modulus `m = a * 2^(w * r) - 1` and the multiplier `2^-w mod m`. The state `x1 .. xr, c`
+//! (where `xr` is the oldest word) corresponds to the residue `c + a * (xr + 2^w * x(r-1) + ...)`.
+//! So `n` steps of the MWC are a multiplication of the residue by `2^(-w * n) mod m`, which can be
+//! computed in logarithmic time.
+//!
+//! Values are stored as little endian 64 bit limbs. `N` must leave at least 128 bits of headroom
+//! above the residue, so that products with a single limb can be reduced.
+
+#[derive(Clone, Copy)]
+pub(crate) struct Modulus<const N: usize> {
+    a: u64,
+    k: u32,
+    w: u32,
+    m: [u64; N],
+}
+
+impl<const N: usize> Modulus<N> {
+    /// The modulus for a MWC generator with `multiplier`, `w` bit words and the given `lag`.
+    pub(crate) fn new(multiplier: u64, w: u32, lag: u32) -> Self {
+        let k = w * lag;
+        debug_assert!(k + 128 <= 64 * N as u32);
+        let mut m = [0; N];
+        or_shifted(&mut m, multiplier, k);
+        sub_assign(&mut m, &one());
+        Modulus { a: multiplier, k, w, m }
+    }
+
+    /// Converts the words of a MWC state (as an integer with the oldest word in the lowest bits) and its
+    /// carry into the corresponding residue.
+    #[inline]
+    pub(crate) fn residue_of(&self, x: [u64; N], c: u64) -> [u64; N] {
+        let mut s = mul_small(&x, self.a);
+        add_small(&mut s, c);
+        s
+    }
+
+    /// The inverse of `residue_of`. For a residue in `0..=m` this returns the unique state with `c < a`.
+    #[inline]
+    pub(crate) fn split(&self, s: [u64; N]) -> ([u64; N], u64) {
+        div_small(&s, self.a)
+    }
+
+    /// Moves the residue `delta` steps forward.
+    pub(crate) fn advance(&self, s: [u64; N], delta: u128) -> [u64; N] {
+        // 2^-w = a * 2^(w * (r - 1)) because a * 2^(w * r) = 1 mod m.
+        let mut inverse_base = [0; N];
+        or_shifted(&mut inverse_base, self.a, self.k - self.w);
+        self.mul_mod(&s, &self.pow(&inverse_base, delta))
+    }
+
+    /// Reduces a value less than `m * 2^64`.
+    fn reduce(&self, mut y: [u64; N]) -> [u64; N] {
+        // With y = h * 2^k + l and q = h / a it follows y - q * m = (h % a) * 2^k + l + q
+        let h = bits_128(&y, self.k);
+        let q = (h / self.a as u128) as u64;
+        let rem = (h % self.a as u128) as u64;
+        for (i, limb) in y.iter_mut().enumerate() {
+            let start = 64 * i as u32;
+            if start >= self.k {
+                *limb = 0;
+            } else if start + 64 > self.k {
+                *limb &= u64::MAX >> (start + 64 - self.k);
+            }
+        }
+        or_shifted(&mut y, rem, self.k);
+        add_small(&mut y, q);
+        if !less_than(&y, &self.m) {
+            sub_assign(&mut y, &self.m);
+        }
+        y
+    }
+
+    fn mul_mod(&self, x: &[u64; N], y: &[u64; N]) -> [u64; N] {
+        let mut acc = [0; N];
+        for &limb in y.iter().rev() {
+            let mut shifted = [0; N];
+            shifted[1..].copy_from_slice(&acc[..N - 1]);
+            acc = self.reduce(shifted);
+            let product = self.reduce(mul_small(x, limb));
+            add_assign(&mut acc, &product);
+            if !less_than(&acc, &self.m) {
+                sub_assign(&mut acc, &self.m);
+            }
+        }
+        acc
+    }
+
+    fn pow(&self, base: &[u64; N], exponent: u128) -> [u64; N] {
+        let mut result = one();
+        for bit in (0..128 - exponent.leading_zeros()).rev() {
+            result = self.mul_mod(&result, &result);
+            if (exponent >> bit) & 1 == 1 {
+                result = self.mul_mod(&result, base);
+            }
+        }
+        result
+    }
+}
+
+#[inline]
+fn one<const N: usize>() -> [u64; N] {
+    let mut result = [0; N];
+    result[0] = 1;
+    result
+}
+
+#[inline]
+fn limb<const N: usize>(x: &[u64; N], i: usize) -> u64 {
+    if i < N {
+        x[i]
+    } else {
+        0
+    }
+}
+
+/// Returns the 128 bits of `x` starting at bit `shift`.
+pub(crate) fn bits_128<const N: usize>(x: &[u64; N], shift: u32) -> u128 {
+    let (i, offset) = ((shift / 64) as usize, shift % 64);
+    let word = |i: usize| {
+        if offset == 0 {
+            limb(x, i)
+        } else {
+            (limb(x, i) >> offset) | (limb(x, i + 1) << (64 - offset))
+        }
+    };
+    (word(i) as u128) | ((word(i + 1) as u128) << 64)
+}
+
+/// Ors `value << shift` into `x`.
+fn or_shifted<const N: usize>(x: &mut [u64; N], value: u64, shift: u32) {
+    let (i, offset) = ((shift / 64) as usize, shift % 64);
+    x[i] |= value << offset;
+    if offset != 0 && i + 1 < N {
+        x[i + 1] |= value >> (64 - offset);
+    }
+}
+
+/// Ors `value << shift` into `x`, where `x` must have room for all 128 bits of `value`.
+pub(crate) fn or_shifted_128<const N: usize>(x: &mut [u64; N], value: u128, shift: u32) {
+    or_shifted(x, value as u64, shift);
+    or_shifted(x, (value >> 64) as u64, shift + 64);
+}
+
+fn less_than<const N: usize>(x: &[u64; N], y: &[u64; N]) -> bool {
+    for i in (0..N).rev() {
+        if x[i] != y[i] {
+            return x[i] < y[i];
+        }
+    }
+    false
+}
+
+fn add_small<const N: usize>(x: &mut [u64; N], value: u64) {
+    let mut carry = value;
+    for limb in x.iter_mut() {
+        let (sum, overflow) = limb.overflowing_add(carry);
+        *limb = sum;
+        carry = overflow as u64;
+    }
+}
+
+fn add_assign<const N: usize>(x: &mut [u64; N], y: &[u64; N]) {
+    let mut carry = false;
+    for i in 0..N {
+        let (sum, b1) = x[i].overflowing_add(y[i]);
+        let (sum, b2) = sum.overflowing_add(carry as u64);
+        x[i] = sum;
+        carry = b1 | b2;
+    }
+}
+
+fn sub_assign<const N: usize>(x: &mut [u64; N], y: &[u64; N]) {
+    let mut borrow = false;
+    for i in 0..N {
+        let (diff, b1) = x[i].overflowing_sub(y[i]);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        x[i] = diff;
+        borrow = b1 | b2;
+    }
+}
+
+fn mul_small<const N: usize>(x: &[u64; N], value: u64) -> [u64; N] {
+    let mut result = [0; N];
+    let mut carry = 0u64;
+    for i in 0..N {
+        let t = (x[i] as u128) * (value as u128) + carry as u128;
+        result[i] = t as u64;
+        carry = (t >> 64) as u64;
+    }
+    result
+}
+
+fn div_small<const N: usize>(x: &[u64; N], divisor: u64) -> ([u64; N], u64) {
+    let mut result = [0; N];
+    let mut rem = 0u64;
+    for i in (0..N).rev() {
+        let t = ((rem as u128) << 64) | x[i] as u128;
+        result[i] = (t / divisor as u128) as u64;
+        rem = (t % divisor as u128) as u64;
+    }
+    (result, rem)
+}
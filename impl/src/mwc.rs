@@ -0,0 +1,939 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The generic multiply with carry generator which `Mwc256XXA64` and `Mwc128XXA32` are instances of.
+
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::BitXor;
+use rand_core::{Error, RngCore};
+
+use crate::mcg::{bits_128, or_shifted_128, Modulus};
+use crate::uninit::{as_uninit, assume_init, write_slice};
+use crate::{InvalidState, SeedSequence};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer type which can be used as the word of a `Mwc` generator.
+///
+/// This is implemented for `u8`, `u16`, `u32`, `u64` and `u128`, and cannot be implemented outside of this crate.
+pub trait MwcWord: private::Sealed + Copy + Eq + BitXor<Output = Self> + Send + Sync + 'static {
+    /// The size of the word in bits.
+    const BITS: u32;
+    /// The value with all bits set.
+    const MAX: Self;
+
+    /// Returns the low and high words of `self * multiplier`.
+    fn mul_wide(self, multiplier: u64) -> (Self, Self);
+
+    /// Adds `rhs`, and returns whether the result overflowed.
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+
+    /// Adds `rhs`, wrapping around on overflow.
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// Returns the low bits of `value`.
+    fn from_u128(value: u128) -> Self;
+
+    /// Returns the value as a `u128`.
+    fn to_u128(self) -> u128;
+}
+
+macro_rules! impl_small_word {
+    ($word:ty) => {
+        impl private::Sealed for $word {}
+
+        impl MwcWord for $word {
+            const BITS: u32 = <$word>::BITS;
+            const MAX: Self = <$word>::MAX;
+
+            #[inline(always)]
+            fn mul_wide(self, multiplier: u64) -> (Self, Self) {
+                // The multiplier is less than 2^BITS, so the product fits in a u64.
+                let t = (self as u64).wrapping_mul(multiplier);
+                (t as $word, (t >> <$word>::BITS) as $word)
+            }
+
+            #[inline(always)]
+            fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+                <$word>::overflowing_add(self, rhs)
+            }
+
+            #[inline(always)]
+            fn wrapping_add(self, rhs: Self) -> Self {
+                <$word>::wrapping_add(self, rhs)
+            }
+
+            #[inline(always)]
+            fn from_u128(value: u128) -> Self {
+                value as $word
+            }
+
+            #[inline(always)]
+            fn to_u128(self) -> u128 {
+                self as u128
+            }
+        }
+    };
+}
+
+impl_small_word!(u8);
+impl_small_word!(u16);
+impl_small_word!(u32);
+
+impl private::Sealed for u64 {}
+
+impl MwcWord for u64 {
+    const BITS: u32 = 64;
+    const MAX: Self = u64::MAX;
+
+    #[inline(always)]
+    fn mul_wide(self, multiplier: u64) -> (Self, Self) {
+        // 128 bit multiplication is a library call on 32 bit targets, so there it is built from 32 bit products.
+        if cfg!(any(feature = "mul32", not(target_pointer_width = "64"))) {
+            multiply_32(self, multiplier)
+        } else {
+            multiply_128(self, multiplier)
+        }
+    }
+
+    #[inline(always)]
+    fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        u64::overflowing_add(self, rhs)
+    }
+
+    #[inline(always)]
+    fn wrapping_add(self, rhs: Self) -> Self {
+        u64::wrapping_add(self, rhs)
+    }
+
+    #[inline(always)]
+    fn from_u128(value: u128) -> Self {
+        value as u64
+    }
+
+    #[inline(always)]
+    fn to_u128(self) -> u128 {
+        self as u128
+    }
+}
+
+impl private::Sealed for u128 {}
+
+impl MwcWord for u128 {
+    const BITS: u32 = 128;
+    const MAX: Self = u128::MAX;
+
+    #[inline(always)]
+    fn mul_wide(self, multiplier: u64) -> (Self, Self) {
+        // The product is 192 bits, assembled from the products of the two halves.
+        let p0 = (self as u64).mul_wide(multiplier);
+        let p1 = ((self >> 64) as u64).mul_wide(multiplier);
+        let (mid, b) = p0.1.overflowing_add(p1.0);
+        let hi = p1.1 + b as u64;
+        (p0.0 as u128 | (mid as u128) << 64, hi as u128)
+    }
+
+    #[inline(always)]
+    fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        u128::overflowing_add(self, rhs)
+    }
+
+    #[inline(always)]
+    fn wrapping_add(self, rhs: Self) -> Self {
+        u128::wrapping_add(self, rhs)
+    }
+
+    #[inline(always)]
+    fn from_u128(value: u128) -> Self {
+        value
+    }
+
+    #[inline(always)]
+    fn to_u128(self) -> u128 {
+        self
+    }
+}
+
+#[inline(always)]
+fn multiply_128(val: u64, multiplier: u64) -> (u64, u64) {
+    //While this looks like 128 bit math, it compiles to a 64 bit multiply.
+    let t = (val as u128).wrapping_mul(multiplier as u128);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+fn multiply_32(val: u64, multiplier: u64) -> (u64, u64) {
+    const LOW_MASK: u64 = 0xffff_ffff;
+    let (a_low, a_high) = (multiplier & LOW_MASK, multiplier >> 32);
+    let (x_low, x_high) = (val & LOW_MASK, val >> 32);
+    let p0 = x_low * a_low;
+    let p1 = x_low * a_high;
+    let p2 = x_high * a_low;
+    let p3 = x_high * a_high;
+    // Each of these sums is less than 2^64, so no carries are lost.
+    let mid = p1 + (p0 >> 32) + (p2 & LOW_MASK);
+    let hi = p3 + (mid >> 32) + (p2 >> 32);
+    ((mid << 32) | (p0 & LOW_MASK), hi)
+}
+
+/// A permuted multiply with carry generator with `LAG` words of type `W`, and an output the size of one word.
+///
+/// `Mwc256XXA64` and `Mwc128XXA32` are instances of this type, and other instances can be used for variants with
+/// different word sizes or lags. Each step multiplies the oldest word by `MULTIPLIER` and the output is
+/// `(oldest ^ second oldest) + (newest ^ high half of the product)`.
+///
+/// The multiplier must be less than `2^W::BITS`, and `LAG` must be at least 2. For the generator to have the full
+/// period of `(MULTIPLIER * 2^(W::BITS * LAG) - 2) / 2`, the modulus `MULTIPLIER * 2^(W::BITS * LAG) - 1` must be a
+/// safe prime, and for good quality the multiplier should also have good spectra. (`parm_search.rs` in the
+/// repository searches for such multipliers.) Neither is checked here, so prefer one of the named generators
+/// unless you have verified the parameters.
+///
+/// All of the named generators have full period multipliers, but only those of `Mwc256XXA64` and `Mwc128XXA32` were
+/// selected for their spectra. The multipliers of `Mwc192XXA64`, `Mwc320XXA64`, `Mwc1024XXA64` and `Mwc512XXA128`
+/// were only checked to give a full period.
+///
+/// With `COMPLEMENT` set each new word is the complement of the low half of `MULTIPLIER * oldest + carry`, which
+/// makes it Marsaglia's complementary multiply with carry generator. See `Cmwc`, which names these instances, for
+/// the conditions on its parameters.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mwc<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool = false> {
+    /// The words, with the most recent first.
+    pub(crate) x: [W; LAG],
+    pub(crate) c: W,
+}
+
+impl<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool> Mwc<W, LAG, MULTIPLIER, COMPLEMENT> {
+    // Evaluated when the generator is constructed, so invalid parameters fail to compile.
+    const VALID_PARAMETERS: () = assert!(
+        LAG >= 2 && MULTIPLIER > 2 && (W::BITS >= 64 || MULTIPLIER >> W::BITS == 0),
+        "LAG must be at least 2 and MULTIPLIER must fit in a word"
+    );
+
+    /// Constructs a generator from its words (most recent first) and carry, without any additional mixing.
+    ///
+    /// The carry must be less than `MULTIPLIER`. With that restriction every residue of the equivalent MCG
+    /// has exactly one representation, except zero which is represented by both the all zero and the all ones
+    /// (with the carry equal to `MULTIPLIER - 1`) states. These are fixed points of the generator, so they are rejected.
+    /// The complementary generator has no fixed points, so for it every state with a small enough carry is accepted.
+    pub fn try_from_words(x: [W; LAG], c: W) -> Result<Self, InvalidState> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_PARAMETERS;
+        if c.to_u128() >= MULTIPLIER as u128 {
+            return Err(InvalidState::CarryTooLarge);
+        }
+        if COMPLEMENT {
+            return Ok(Mwc { x, c });
+        }
+        let zero = W::from_u128(0);
+        let all_zero = x.iter().all(|&w| w == zero) && c == zero;
+        let all_ones = x.iter().all(|&w| w == W::MAX) && c == W::from_u128(MULTIPLIER as u128 - 1);
+        if all_zero || all_ones {
+            return Err(InvalidState::FixedPoint);
+        }
+        Ok(Mwc { x, c })
+    }
+
+    /// Returns the words (most recent first) and the carry. The result can be turned back into an identical
+    /// generator using `try_from_words`.
+    pub fn to_words(&self) -> ([W; LAG], W) {
+        (self.x, self.c)
+    }
+
+    /// Construct an instance from a seed sequence, using its output for the full state.
+    pub fn from_seed_sequence(seq: &SeedSequence) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_PARAMETERS;
+        let mut words = vec![W::from_u128(0); LAG + 1];
+        generate_words(seq, &mut words);
+        let c = if COMPLEMENT {
+            W::from_u128(words[LAG].to_u128() % MULTIPLIER as u128)
+        } else {
+            // A carry in 1..MULTIPLIER - 1 is never a fixed point.
+            W::from_u128(words[LAG].to_u128() % (MULTIPLIER as u128 - 2) + 1)
+        };
+        let mut x = [W::from_u128(0); LAG];
+        x.copy_from_slice(&words[..LAG]);
+        Mwc { x, c }
+    }
+
+    /// Constructs a generator from the given state, and advances it 6 steps to fully mix the keys.
+    #[inline]
+    pub(crate) fn from_state_incr(x: [W; LAG], c: W) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_PARAMETERS;
+        let mut pcg = Mwc { x, c };
+        pcg.gen6();
+        pcg
+    }
+
+    /// Splits off a child generator, advancing this one by `LAG` steps.
+    ///
+    /// The child's words are taken from the next `LAG` outputs of the parent and its carry is a constant which
+    /// `new`, `with_stream` and `from_seed` of `Mwc256XXA64` and `Mwc128XXA32` never use, so children of these never
+    /// start from the same state as a generator created with them. Repeated splits form a tree which only depends
+    /// on the root's state, which makes this suitable for recursive divide and conquer algorithms.
+    pub fn split(&mut self) -> Self {
+        let x = self.gen_array();
+        // The top bits of the fractional part of the golden ratio, as a carry in 1..MULTIPLIER - 1 so that the
+        // child is never a fixed point.
+        let c = (0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c834 >> (128 - W::BITS)) % (MULTIPLIER as u128 - 2) + 1;
+        Mwc::from_state_incr(x, W::from_u128(c))
+    }
+
+    /// Steps the generator backwards, and returns the value `next_u32` returned from the restored state.
+    ///
+    /// This is the inverse of `next_u32`: calling `next_u32` followed by `prev_u32` returns the same value
+    /// twice and leaves the generator in its original state.
+    pub fn prev_u32(&mut self) -> u32 {
+        self.prev_int()
+    }
+
+    /// Steps the generator backwards, and returns the value `next_u64` returned from the restored state.
+    ///
+    /// This is the inverse of `next_u64`: calling `next_u64` followed by `prev_u64` returns the same value
+    /// twice and leaves the generator in its original state.
+    pub fn prev_u64(&mut self) -> u64 {
+        self.prev_int()
+    }
+
+    /// Fills `dest` with 32-bit words. Outputs are split or combined as needed, with the first in the lowest bits,
+    /// so the result is the same as `fill_bytes` read as little endian words.
+    #[inline]
+    pub fn fill_u32(&mut self, dest: &mut [u32]) {
+        self.fill_words_uninit(as_uninit(dest));
+    }
+
+    /// Like `fill_u32`, but `dest` does not need to be initialized. Returns `dest` as an initialized slice.
+    #[inline]
+    pub fn fill_u32_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u32>]) -> &'a mut [u32] {
+        self.fill_words_uninit(dest)
+    }
+
+    /// Fills `dest` with 64-bit words. Outputs are split or combined as needed, with the first in the lowest bits,
+    /// so the result is the same as `fill_bytes` read as little endian words.
+    #[inline]
+    pub fn fill_u64(&mut self, dest: &mut [u64]) {
+        self.fill_words_uninit(as_uninit(dest));
+    }
+
+    /// Like `fill_u64`, but `dest` does not need to be initialized. Returns `dest` as an initialized slice.
+    #[inline]
+    pub fn fill_u64_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u64>]) -> &'a mut [u64] {
+        self.fill_words_uninit(dest)
+    }
+
+    /// Fills `dest` with 128-bit words. Outputs are split or combined as needed, with the first in the lowest bits,
+    /// so the result is the same as `fill_bytes` read as little endian words.
+    #[inline]
+    pub fn fill_u128(&mut self, dest: &mut [u128]) {
+        self.fill_words_uninit(as_uninit(dest));
+    }
+
+    /// Like `fill_u128`, but `dest` does not need to be initialized. Returns `dest` as an initialized slice.
+    #[inline]
+    pub fn fill_u128_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u128>]) -> &'a mut [u128] {
+        self.fill_words_uninit(dest)
+    }
+
+    /// Like `fill_bytes`, but `dest` does not need to be initialized. Returns `dest` as an initialized slice.
+    #[inline]
+    pub fn fill_bytes_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>]) -> &'a mut [u8] {
+        let bytes = (W::BITS / 8) as usize;
+        let mut dest_chunks = dest.chunks_exact_mut(6 * bytes);
+        for dest_chunk in &mut dest_chunks {
+            for (word, &num) in dest_chunk.chunks_exact_mut(bytes).zip(self.gen6().iter()) {
+                write_le_bytes(num, word);
+            }
+        }
+        for word in dest_chunks.into_remainder().chunks_mut(bytes) {
+            write_le_bytes(self.step(), word);
+        }
+        // Safety: every element was written above.
+        unsafe { assume_init(dest) }
+    }
+
+    /// Returns the next `N` outputs, in order.
+    ///
+    /// Like the internal six step path, the state is kept in local variables for the whole batch rather than
+    /// being written back after every step, so this is faster than generating the outputs one at a time.
+    #[inline]
+    pub fn gen_array<const N: usize>(&mut self) -> [W; N] {
+        let (mut x, mut c) = (self.x, self.c);
+        let result = core::array::from_fn(|_| step::<W, LAG, MULTIPLIER, COMPLEMENT>(&mut x, &mut c));
+        self.x = x;
+        self.c = c;
+        result
+    }
+
+    #[inline]
+    pub(crate) fn gen6(&mut self) -> [W; 6] {
+        self.gen_array()
+    }
+
+    #[inline]
+    pub(crate) fn step(&mut self) -> W {
+        step::<W, LAG, MULTIPLIER, COMPLEMENT>(&mut self.x, &mut self.c)
+    }
+
+    /// Returns the next output as a `T`. Wider outputs are truncated, and narrower ones are concatenated with
+    /// the first in the highest bits.
+    #[inline]
+    fn next_int<T: MwcWord>(&mut self) -> T {
+        if W::BITS >= T::BITS {
+            return T::from_u128(self.step().to_u128());
+        }
+        let (mut x, mut c) = (self.x, self.c);
+        let mut result = 0;
+        for _ in 0..T::BITS / W::BITS {
+            result = result << W::BITS | step::<W, LAG, MULTIPLIER, COMPLEMENT>(&mut x, &mut c).to_u128();
+        }
+        self.x = x;
+        self.c = c;
+        T::from_u128(result)
+    }
+
+    /// The inverse of `next_int`.
+    #[inline]
+    fn prev_int<T: MwcWord>(&mut self) -> T {
+        if W::BITS >= T::BITS {
+            return T::from_u128(self.prev().to_u128());
+        }
+        // The last output of `next_int` is in the lowest bits, and is the first to be restored.
+        let mut result = 0;
+        for i in 0..T::BITS / W::BITS {
+            result |= self.prev().to_u128() << (W::BITS * i);
+        }
+        T::from_u128(result)
+    }
+
+    /// Steps the generator backwards, and returns the output of the restored state.
+    fn prev(&mut self) -> W {
+        // The last step computed `MULTIPLIER * oldest + c` and stored it as `(c << W::BITS) | x1`, with `x1`
+        // complemented for the complementary generator. Because c < MULTIPLIER the previous oldest word and carry
+        // can be recovered by division, which is done in chunks of at most 64 bits so the dividend fits in a u128.
+        let x1 = if COMPLEMENT { self.x[0] ^ W::MAX } else { self.x[0] }.to_u128();
+        let chunk_bits = W::BITS.min(64);
+        let mask = u128::MAX >> (128 - chunk_bits);
+        let (mut oldest, mut c) = (0, self.c.to_u128());
+        for i in (0..W::BITS / chunk_bits).rev() {
+            let t = c << chunk_bits | (x1 >> (chunk_bits * i) & mask);
+            oldest |= (t / MULTIPLIER as u128) << (chunk_bits * i);
+            c = t % MULTIPLIER as u128;
+        }
+        self.x = core::array::from_fn(|i| if i + 1 < LAG { self.x[i + 1] } else { W::from_u128(oldest) });
+        self.c = W::from_u128(c);
+        let (_, hi) = self.x[LAG - 1].mul_wide(MULTIPLIER);
+        permute(&self.x, hi)
+    }
+
+    /// Fills `dest` with the outputs, split or combined into values of type `T` with the first output in the
+    /// lowest bits.
+    #[inline]
+    fn fill_words_uninit<'a, T: MwcWord>(&mut self, dest: &'a mut [MaybeUninit<T>]) -> &'a mut [T] {
+        if T::BITS <= W::BITS {
+            let per_output = (W::BITS / T::BITS) as usize;
+            let mut dest_chunks = dest.chunks_exact_mut(6 * per_output);
+            for dest_chunk in &mut dest_chunks {
+                for (values, &num) in dest_chunk.chunks_exact_mut(per_output).zip(self.gen6().iter()) {
+                    write_split(values, num);
+                }
+            }
+            // A partial final chunk still uses a whole output.
+            for values in dest_chunks.into_remainder().chunks_mut(per_output) {
+                write_split(values, self.step());
+            }
+        } else {
+            let per_value = (T::BITS / W::BITS) as usize;
+            let mut dest_chunks = dest.chunks_exact_mut(6);
+            for dest_chunk in &mut dest_chunks {
+                let mut values = [0u128; 6];
+                for i in 0..per_value {
+                    for (j, &num) in self.gen6().iter().enumerate() {
+                        let k = 6 * i + j;
+                        values[k / per_value] |= num.to_u128() << (W::BITS * (k % per_value) as u32);
+                    }
+                }
+                write_slice(dest_chunk, &values.map(T::from_u128));
+            }
+            for value in dest_chunks.into_remainder() {
+                let mut result = 0;
+                for i in 0..per_value {
+                    result |= self.step().to_u128() << (W::BITS * i as u32);
+                }
+                value.write(T::from_u128(result));
+            }
+        }
+        // Safety: every element was written above.
+        unsafe { assume_init(dest) }
+    }
+}
+
+// The states are arrays of `LAG + 1` words, which can't be written for a generic lag, so these are only provided
+// for lag 3. `to_words` and `try_from_words` work for any lag.
+impl<W: MwcWord, const MULTIPLIER: u64, const COMPLEMENT: bool> Mwc<W, 3, MULTIPLIER, COMPLEMENT> {
+    /// Returns the internal state as `[x1, x2, x3, c]`, where `x1` is the most recent word.
+    ///
+    /// The result can be turned back into an identical generator using `try_from_state`.
+    pub fn to_state(&self) -> [W; 4] {
+        let [x1, x2, x3] = self.x;
+        [x1, x2, x3, self.c]
+    }
+
+    /// Constructs a generator from a state produced by `to_state`, without any additional mixing.
+    ///
+    /// The same states are rejected as by `try_from_words`.
+    pub fn try_from_state(state: [W; 4]) -> Result<Self, InvalidState> {
+        let [x1, x2, x3, c] = state;
+        Mwc::try_from_words([x1, x2, x3], c)
+    }
+}
+
+// The complementary generator is not equivalent to the same MCG, so it can't be advanced this way.
+impl<W: MwcWord, const LAG: usize, const MULTIPLIER: u64> Mwc<W, LAG, MULTIPLIER> {
+    /// Advances the generator `delta` steps, as if `delta` outputs were generated. (For generators with words
+    /// narrower than 64 bits `next_u64` uses several outputs.)
+    ///
+    /// This runs in `O(log(delta))` time by using the equivalence of the MWC generator to a MCG with the
+    /// modulus `MULTIPLIER * 2^(W::BITS * LAG) - 1`.
+    ///
+    /// Panics if the words of the generator have more than 4096 bits in total.
+    pub fn advance(&mut self, delta: u128) {
+        // `Modulus` needs 128 bits of headroom above the words. The number of limbs has to be a constant, and
+        // the time taken grows with its square, so use the smallest of a few sizes which is large enough.
+        match (W::BITS as usize * LAG + 128).div_ceil(64) {
+            0..=4 => self.advance_in::<4>(delta),
+            5 => self.advance_in::<5>(delta),
+            6 => self.advance_in::<6>(delta),
+            7..=8 => self.advance_in::<8>(delta),
+            9..=17 => self.advance_in::<17>(delta),
+            18..=33 => self.advance_in::<33>(delta),
+            34..=66 => self.advance_in::<66>(delta),
+            _ => panic!("advance supports at most 4096 bits of words"),
+        }
+    }
+
+    fn advance_in<const N: usize>(&mut self, delta: u128) {
+        let modulus = Modulus::<N>::new(MULTIPLIER, W::BITS, LAG as u32);
+        // The MCG residue has the oldest word in the lowest bits.
+        let mut x = [0; N];
+        for (i, &word) in self.x.iter().rev().enumerate() {
+            or_shifted_128(&mut x, word.to_u128(), W::BITS * i as u32);
+        }
+        let (x, c) = modulus.split(modulus.advance(modulus.residue_of(x, self.c.to_u128() as u64), delta));
+        for (i, word) in self.x.iter_mut().rev().enumerate() {
+            *word = W::from_u128(bits_128(&x, W::BITS * i as u32));
+        }
+        self.c = W::from_u128(c as u128);
+    }
+}
+
+/// Advances the words and carry by one step, and returns the output.
+#[inline(always)]
+fn step<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+    x: &mut [W; LAG],
+    c: &mut W,
+) -> W {
+    // prepare the MCG for the next round
+    let (low, hi) = x[LAG - 1].mul_wide(MULTIPLIER);
+    let result = permute(x, hi);
+    let (r, b) = low.overflowing_add(*c);
+    let r = if COMPLEMENT { r ^ W::MAX } else { r };
+    *x = core::array::from_fn(|i| if i == 0 { r } else { x[i - 1] });
+    *c = hi.wrapping_add(W::from_u128(b as u128));
+    result
+}
+
+/// The output permutation, where `hi` is the high half of the product of the oldest word and the multiplier.
+#[inline(always)]
+pub(crate) fn permute<W: MwcWord, const LAG: usize>(x: &[W; LAG], hi: W) -> W {
+    (x[LAG - 1] ^ x[LAG - 2]).wrapping_add(x[0] ^ hi)
+}
+
+/// Writes the first `dest.len()` bytes of the little endian representation of `word`.
+#[inline(always)]
+fn write_le_bytes<W: MwcWord>(word: W, dest: &mut [MaybeUninit<u8>]) {
+    write_slice(dest, &word.to_u128().to_le_bytes());
+}
+
+/// Copies the first `dest.len()` bytes of the little endian representation of `word` into `dest`.
+#[inline(always)]
+fn copy_le_bytes<W: MwcWord>(word: W, dest: &mut [u8]) {
+    let len = dest.len();
+    let value = word.to_u128();
+    // Convert from the narrowest type which holds the word, so the copy is a single store.
+    match W::BITS {
+        8 => dest.copy_from_slice(&(value as u8).to_le_bytes()[..len]),
+        16 => dest.copy_from_slice(&(value as u16).to_le_bytes()[..len]),
+        32 => dest.copy_from_slice(&(value as u32).to_le_bytes()[..len]),
+        64 => dest.copy_from_slice(&(value as u64).to_le_bytes()[..len]),
+        _ => dest.copy_from_slice(&value.to_le_bytes()[..len]),
+    }
+}
+
+/// Fills `dest` from a seed sequence. Words of up to 32 bits are each made from one 32-bit word, and larger ones
+/// from 64-bit words, low half first.
+fn generate_words<W: MwcWord>(seq: &SeedSequence, dest: &mut [W]) {
+    if W::BITS <= 32 {
+        let mut words = vec![0; dest.len()];
+        seq.generate_u32(&mut words);
+        for (word, &value) in dest.iter_mut().zip(words.iter()) {
+            *word = W::from_u128(value as u128);
+        }
+    } else {
+        let per_word = (W::BITS / 64) as usize;
+        let mut words = vec![0; dest.len() * per_word];
+        seq.generate_u64(&mut words);
+        for (word, values) in dest.iter_mut().zip(words.chunks_exact(per_word)) {
+            *word = W::from_u128(values.iter().rev().fold(0, |acc, &value| acc << 64 | value as u128));
+        }
+    }
+}
+
+/// Writes the pieces of `num` to `dest`, low bits first.
+#[inline(always)]
+fn write_split<W: MwcWord, T: MwcWord>(dest: &mut [MaybeUninit<T>], num: W) {
+    let num = num.to_u128();
+    for (i, value) in dest.iter_mut().enumerate() {
+        value.write(T::from_u128(num >> (T::BITS * i as u32)));
+    }
+}
+
+// Custom Debug implementation that does not expose the internal state
+impl<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool> fmt::Debug for Mwc<W, LAG, MULTIPLIER, COMPLEMENT> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = if COMPLEMENT { "Cmwc" } else { "Mwc" };
+        write!(f, "{}{}XXA{} {{}}", name, W::BITS * (LAG as u32 + 1), W::BITS)
+    }
+}
+
+/// Outputs wider than the requested integer are truncated, and narrower ones are concatenated with the
+/// first output in the highest bits. `fill_bytes` uses every byte of each output, in little endian order.
+impl<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool> RngCore for Mwc<W, LAG, MULTIPLIER, COMPLEMENT> {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.next_int()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.next_int()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        // The same as `fill_bytes_uninit`, but writing to initialized memory directly.
+        let bytes = (W::BITS / 8) as usize;
+        let mut dest_chunks = dest.chunks_exact_mut(6 * bytes);
+        for dest_chunk in &mut dest_chunks {
+            for (word, &num) in dest_chunk.chunks_exact_mut(bytes).zip(self.gen6().iter()) {
+                copy_le_bytes(num, word);
+            }
+        }
+        for word in dest_chunks.into_remainder().chunks_mut(bytes) {
+            copy_le_bytes(self.step(), word);
+        }
+    }
+
+    #[inline(always)]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use core::convert::TryInto;
+    use rand_core::SeedableRng;
+
+    use crate::{Cmwc128XXA32, Cmwc256XXA64, Mwc128XXA32, Mwc256XXA64, Mwc512XXA128};
+
+    /// Returns the fraction of bits which match between the outputs of `a` and `b`, which is close to 0.5 for
+    /// unrelated generators.
+    pub(crate) fn matching_bit_fraction<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+        a: &mut Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+        b: &mut Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) -> f64 {
+        let samples = 10_000;
+        let differing: u32 = (0..samples).map(|_| (a.step() ^ b.step()).to_u128().count_ones()).sum();
+        1.0 - differing as f64 / (samples * W::BITS) as f64
+    }
+
+    /// Implemented for every `Mwc`, so the helpers below only need the type of the generator.
+    pub(crate) trait WordsRoundTrip {
+        /// Returns whether `try_from_words` accepts the words of `self` and recreates it.
+        fn words_round_trip(&self) -> bool;
+    }
+
+    impl<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool> WordsRoundTrip
+        for Mwc<W, LAG, MULTIPLIER, COMPLEMENT>
+    {
+        fn words_round_trip(&self) -> bool {
+            let (x, c) = self.to_words();
+            Self::try_from_words(x, c) == Ok(self.clone())
+        }
+    }
+
+    /// Checks that seeding gives states which `try_from_words` accepts, so the carry is less than `MULTIPLIER` and
+    /// the state is not a fixed point, including for the all zero and all ones seeds.
+    pub(crate) fn check_seeded_states_valid<R: SeedableRng + WordsRoundTrip>() {
+        let mut all_ones = R::Seed::default();
+        all_ones.as_mut().fill(0xff);
+        let seeded = [0, 1, u64::MAX].map(R::seed_from_u64);
+        for rng in seeded.iter().chain(&[R::from_seed(R::Seed::default()), R::from_seed(all_ones)]) {
+            assert!(rng.words_round_trip());
+        }
+    }
+
+    /// Checks that the first outputs of `a` and `b` differ.
+    pub(crate) fn check_outputs_differ<R: RngCore>(mut a: R, mut b: R) {
+        for _ in 0..10 {
+            assert_ne!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    pub(crate) fn check_split<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+        mut parent: Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) {
+        let mut copy = parent.clone();
+        let mut child = parent.split();
+        assert!(child == copy.split());
+        let mut sibling = parent.split();
+        let mut grandchild = child.split();
+        assert!((matching_bit_fraction(&mut parent.clone(), &mut child.clone()) - 0.5).abs() < 0.01);
+        assert!((matching_bit_fraction(&mut child.clone(), &mut sibling.clone()) - 0.5).abs() < 0.01);
+        assert!((matching_bit_fraction(&mut child, &mut grandchild) - 0.5).abs() < 0.01);
+        assert!((matching_bit_fraction(&mut parent, &mut sibling) - 0.5).abs() < 0.01);
+    }
+
+    pub(crate) fn check_state_round_trip<W: MwcWord, const MULTIPLIER: u64>(mut rng: Mwc<W, 3, MULTIPLIER>) {
+        let mut restored = Mwc::<W, 3, MULTIPLIER>::try_from_state(rng.to_state()).unwrap();
+        assert_eq!(rng.next_u64(), restored.next_u64());
+        let (zero, max, carry) = (W::from_u128(0), W::MAX, |c: u64| W::from_u128(c as u128));
+        let check = |state, expected| assert!(Mwc::<W, 3, MULTIPLIER>::try_from_state(state) == expected);
+        check([zero; 4], Err(InvalidState::FixedPoint));
+        check([max, max, max, carry(MULTIPLIER - 1)], Err(InvalidState::FixedPoint));
+        check([max, max, max, carry(MULTIPLIER)], Err(InvalidState::CarryTooLarge));
+        assert!(Mwc::<W, 3, MULTIPLIER>::try_from_state([max, max, max, carry(MULTIPLIER - 2)]).is_ok());
+    }
+
+    pub(crate) fn check_prev_reverses_next<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+        mut rng: Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) {
+        let start = rng.clone();
+        let forward: Vec<u32> = (0..20).map(|_| rng.next_u32()).collect();
+        let backward: Vec<u32> = (0..20).map(|_| rng.prev_u32()).collect();
+        assert!(forward.iter().eq(backward.iter().rev()));
+        let forward: Vec<u64> = (0..20).map(|_| rng.next_u64()).collect();
+        let backward: Vec<u64> = (0..20).map(|_| rng.prev_u64()).collect();
+        assert!(forward.iter().eq(backward.iter().rev()));
+        assert!(rng == start);
+    }
+
+    pub(crate) fn check_advance_matches_step<W: MwcWord, const LAG: usize, const MULTIPLIER: u64>(
+        mut stepped: Mwc<W, LAG, MULTIPLIER>,
+    ) {
+        for delta in 0..50 {
+            let mut jumped = stepped.clone();
+            jumped.advance(delta);
+            let mut expected = stepped.clone();
+            for _ in 0..delta {
+                expected.step();
+            }
+            assert!(jumped == expected);
+            stepped.step();
+        }
+    }
+
+    pub(crate) fn check_advance_composes<W: MwcWord, const LAG: usize, const MULTIPLIER: u64>(
+        mut a: Mwc<W, LAG, MULTIPLIER>,
+    ) {
+        let mut b = a.clone();
+        a.advance(u64::MAX as u128 * 12345);
+        a.advance(u128::MAX - 7);
+        b.advance(u128::MAX);
+        b.advance(u64::MAX as u128 * 12345 - 7);
+        assert!(a == b);
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+
+    fn check_gen_array<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool, const N: usize>(
+        mut rng: Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) {
+        let mut expected = rng.clone();
+        for _ in 0..3 {
+            let values: [W; N] = rng.gen_array();
+            assert!(values.iter().all(|&v| v == expected.step()));
+        }
+        assert!(rng == expected);
+    }
+
+    fn check_gen_arrays<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+        rng: Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) {
+        check_gen_array::<W, LAG, MULTIPLIER, COMPLEMENT, 0>(rng.clone());
+        check_gen_array::<W, LAG, MULTIPLIER, COMPLEMENT, 1>(rng.clone());
+        check_gen_array::<W, LAG, MULTIPLIER, COMPLEMENT, 6>(rng.clone());
+        check_gen_array::<W, LAG, MULTIPLIER, COMPLEMENT, 7>(rng.clone());
+        check_gen_array::<W, LAG, MULTIPLIER, COMPLEMENT, 64>(rng);
+    }
+
+    #[test]
+    fn test_gen_array() {
+        check_gen_arrays(Mwc256XXA64::seed_from_u64(1));
+        check_gen_arrays(Mwc128XXA32::seed_from_u64(1));
+        check_gen_arrays(Cmwc256XXA64::seed_from_u64(1));
+    }
+
+    fn check_typed_fills<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+        rng: Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) {
+        for len in 0..40 {
+            let mut bytes = vec![0u8; len * 16];
+            rng.clone().fill_bytes(&mut bytes);
+            let mut words = vec![0u32; len];
+            rng.clone().fill_u32(&mut words);
+            assert!(words.iter().zip(bytes.chunks_exact(4)).all(|(w, b)| w.to_le_bytes() == b));
+            let mut words = vec![0u64; len];
+            rng.clone().fill_u64(&mut words);
+            assert!(words.iter().zip(bytes.chunks_exact(8)).all(|(w, b)| w.to_le_bytes() == b));
+            let mut words = vec![0u128; len];
+            rng.clone().fill_u128(&mut words);
+            assert!(words.iter().zip(bytes.chunks_exact(16)).all(|(w, b)| w.to_le_bytes() == b));
+        }
+    }
+
+    #[test]
+    fn test_typed_fills() {
+        check_typed_fills(Mwc256XXA64::new(1, 2));
+        check_typed_fills(Mwc128XXA32::new(1, 2));
+        check_typed_fills(Cmwc128XXA32::seed_from_u64(2));
+    }
+
+    fn check_uninit_fills<W: MwcWord, const LAG: usize, const MULTIPLIER: u64, const COMPLEMENT: bool>(
+        rng: Mwc<W, LAG, MULTIPLIER, COMPLEMENT>,
+    ) {
+        for len in 0..40 {
+            let mut bytes = vec![0u8; len * 16 + 3];
+            rng.clone().fill_bytes(&mut bytes);
+            let mut uninit = vec![MaybeUninit::uninit(); len * 16 + 3];
+            assert_eq!(rng.clone().fill_bytes_uninit(&mut uninit), &bytes[..]);
+            let mut words = vec![0u32; len];
+            rng.clone().fill_u32(&mut words);
+            let mut uninit = vec![MaybeUninit::uninit(); len];
+            assert_eq!(rng.clone().fill_u32_uninit(&mut uninit), &words[..]);
+            let mut words = vec![0u64; len];
+            rng.clone().fill_u64(&mut words);
+            let mut uninit = vec![MaybeUninit::uninit(); len];
+            assert_eq!(rng.clone().fill_u64_uninit(&mut uninit), &words[..]);
+            let mut words = vec![0u128; len];
+            rng.clone().fill_u128(&mut words);
+            let mut uninit = vec![MaybeUninit::uninit(); len];
+            assert_eq!(rng.clone().fill_u128_uninit(&mut uninit), &words[..]);
+        }
+    }
+
+    #[test]
+    fn test_uninit_fills() {
+        check_uninit_fills(Mwc256XXA64::new(1, 2));
+        check_uninit_fills(Mwc128XXA32::new(1, 2));
+        check_uninit_fills(Cmwc256XXA64::seed_from_u64(2));
+    }
+
+    #[test]
+    fn test_prev_reverses_next() {
+        check_prev_reverses_next(Mwc::<u8, 3, 228>::from_seed_sequence(&SeedSequence::new(&[1])));
+        check_prev_reverses_next(Mwc512XXA128::seed_from_u64(1));
+        check_prev_reverses_next(Cmwc256XXA64::seed_from_u64(1));
+        check_prev_reverses_next(Cmwc128XXA32::seed_from_u64(1));
+    }
+
+    #[test]
+    fn test_advance_word_sizes() {
+        check_advance_matches_step(Mwc::<u8, 3, 228>::from_seed_sequence(&SeedSequence::new(&[1])));
+        check_advance_matches_step(Mwc::<u16, 3, 39273>::from_seed_sequence(&SeedSequence::new(&[1])));
+        check_advance_matches_step(Mwc512XXA128::seed_from_u64(1));
+    }
+
+    #[test]
+    fn test_split_complementary() {
+        check_split(Cmwc256XXA64::seed_from_u64(11));
+    }
+
+    #[test]
+    fn test_multiply_paths_agree() {
+        let multiplier = crate::gen64::MULTIPLIER;
+        let mut rng = Mwc256XXA64::seed_from_u64(19);
+        let edges = [0, 1, 0xffff_ffff, 1 << 32, u64::MAX - 1, u64::MAX, multiplier];
+        for val in edges.iter().copied().chain((0..10_000).map(|_| rng.next_u64())) {
+            assert_eq!(multiply_32(val, multiplier), multiply_128(val, multiplier));
+        }
+    }
+
+    #[test]
+    fn test_u128_multiply() {
+        let mut rng = Mwc256XXA64::seed_from_u64(20);
+        for _ in 0..10_000 {
+            let (val, multiplier) = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128, rng.next_u64());
+            let (low, hi) = val.mul_wide(multiplier);
+            // Check against the product computed in 64 bit pieces.
+            let expected_low = val.wrapping_mul(multiplier as u128);
+            let expected_hi = ((val >> 64) * multiplier as u128 + ((val as u64 as u128 * multiplier as u128) >> 64)) >> 64;
+            assert_eq!((low, hi), (expected_low, expected_hi));
+        }
+    }
+
+    #[test]
+    fn test_words_round_trip() {
+        type Mwc32XXA8 = Mwc<u8, 3, 228>;
+        let rng = Mwc32XXA8::from_seed_sequence(&SeedSequence::new(&[1]));
+        let (x, c) = rng.to_words();
+        assert_eq!(Mwc32XXA8::try_from_words(x, c).unwrap(), rng);
+        assert_eq!(Mwc32XXA8::try_from_words([0; 3], 0), Err(InvalidState::FixedPoint));
+        assert_eq!(Mwc32XXA8::try_from_words([255; 3], 227), Err(InvalidState::FixedPoint));
+        assert_eq!(Mwc32XXA8::try_from_words([1; 3], 228), Err(InvalidState::CarryTooLarge));
+        assert_eq!(format!("{:?}", rng), "Mwc32XXA8 {}");
+    }
+
+    #[test]
+    fn test_small_words_match_reference() {
+        // The hand written 8 bit generator that the original parameter search was done with.
+        let (mut x1, mut x2, mut x3, mut c) = (123u8, 34u8, 56u8, 78u8);
+        let mut rng = Mwc::<u8, 3, 228>::try_from_words([x1, x2, x3], c).unwrap();
+        for _ in 0..1000 {
+            let t = (x3 as u16) * 228;
+            let (low, hi) = (t as u8, (t >> 8) as u8);
+            let expected = (x3 ^ x2).wrapping_add(x1 ^ hi);
+            let (r, b) = low.overflowing_add(c);
+            x3 = x2;
+            x2 = x1;
+            x1 = r;
+            c = hi.wrapping_add(b as u8);
+            assert_eq!(rng.gen_array::<1>(), [expected]);
+        }
+    }
+
+    #[test]
+    fn test_fill_widths_agree() {
+        let mut bytes = [0u8; 96];
+        let mut words = [0u32; 24];
+        let mut wide = [0u128; 6];
+        let rng = Mwc::<u16, 3, 39273>::from_seed_sequence(&SeedSequence::new(&[1]));
+        rng.clone().fill_bytes(&mut bytes);
+        rng.clone().fill_u32(&mut words);
+        rng.clone().fill_u128(&mut wide);
+        for (i, chunk) in bytes.chunks_exact(4).enumerate() {
+            assert_eq!(u32::from_le_bytes(chunk.try_into().unwrap()), words[i]);
+        }
+        for (i, chunk) in bytes.chunks_exact(16).enumerate() {
+            assert_eq!(u128::from_le_bytes(chunk.try_into().unwrap()), wide[i]);
+        }
+    }
+}
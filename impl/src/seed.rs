@@ -0,0 +1,45 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Seeds longer than 32 bytes. `SeedableRng::Seed` must implement `Default`, which arrays longer than 32 bytes do
+//! not, so they are wrapped.
+
+macro_rules! seed_newtype {
+    ($(#[$attr:meta])* $name:ident, $bytes:literal) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name(pub [u8; $bytes]);
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name([0; $bytes])
+            }
+        }
+
+        impl AsMut<[u8]> for $name {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+    };
+}
+
+seed_newtype!(
+    /// The seed of `Mwc512XXA128`.
+    Seed512,
+    64
+);
+seed_newtype!(
+    /// The seed of `Mwc1024XXA64`.
+    Seed1024,
+    128
+);
@@ -0,0 +1,271 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::fmt;
+
+// The smallest pool, in 32-bit words.
+const POOL_SIZE: usize = 4;
+const INIT_A: u32 = 0x43b0_d7e5;
+const MULT_A: u32 = 0x931e_8875;
+const INIT_B: u32 = 0x8b51_f9dd;
+const MULT_B: u32 = 0x58f3_8ded;
+const MIX_MULT_L: u32 = 0xca01_f9dd;
+const MIX_MULT_R: u32 = 0x4973_f715;
+const XSHIFT: u32 = 16;
+
+/// Hashes an arbitrary amount of entropy into well mixed seeds.
+///
+/// This follows the design of NumPy's `SeedSequence`: the entropy is mixed into a pool using a hash with good
+/// avalanche properties, where every input word affects every pool word. Like NumPy's `pool_size` the pool can be
+/// larger than the default 128 bits, and here it is always as large as the number of words generated, so every
+/// state of the generator being seeded can be reached. Independent child sequences can be spawned, which are
+/// identified by their `spawn_key` (the path of spawn indices from the root).
+///
+/// The length of the entropy is hashed along with it, so inputs which differ only by trailing zeros give
+/// unrelated seeds.
+///
+/// Use `Mwc256XXA64::from_seed_sequence` or `Mwc128XXA32::from_seed_sequence` to create a generator.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SeedSequence {
+    entropy: Vec<u32>,
+    // The length of the entropy in bytes, before it was padded to whole words.
+    entropy_bytes: u64,
+    spawn_key: Vec<u32>,
+    children_spawned: u32,
+}
+
+impl SeedSequence {
+    /// Creates a seed sequence from any number of 32-bit words of entropy.
+    pub fn new(entropy: &[u32]) -> Self {
+        SeedSequence::with_spawn_key(entropy.to_vec(), 4 * entropy.len() as u64, Vec::new())
+    }
+
+    /// Creates a seed sequence from any number of 64-bit words of entropy.
+    /// Each word is split into two 32-bit words, low half first.
+    pub fn from_u64s(entropy: &[u64]) -> Self {
+        let words = entropy.iter().flat_map(|&w| [w as u32, (w >> 32) as u32]).collect();
+        SeedSequence::with_spawn_key(words, 8 * entropy.len() as u64, Vec::new())
+    }
+
+    /// Creates a seed sequence from any number of bytes of entropy.
+    /// The bytes are read as little endian 32-bit words, with the last one padded with zeros.
+    pub fn from_bytes(entropy: &[u8]) -> Self {
+        let words = entropy
+            .chunks(4)
+            .map(|chunk| {
+                let mut word = [0; 4];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u32::from_le_bytes(word)
+            })
+            .collect();
+        SeedSequence::with_spawn_key(words, entropy.len() as u64, Vec::new())
+    }
+
+    fn with_spawn_key(entropy: Vec<u32>, entropy_bytes: u64, spawn_key: Vec<u32>) -> Self {
+        SeedSequence { entropy, entropy_bytes, spawn_key, children_spawned: 0 }
+    }
+
+    /// The spawn indices which lead from the root sequence to this one.
+    pub fn spawn_key(&self) -> &[u32] {
+        &self.spawn_key
+    }
+
+    /// Spawns `count` child sequences. Every child is independent of this sequence and of the other children,
+    /// including those returned by earlier calls.
+    ///
+    /// Panics if more than `u32::MAX` children would be spawned in total, as the spawn indices would repeat.
+    pub fn spawn(&mut self, count: u32) -> Vec<SeedSequence> {
+        let start = self.children_spawned;
+        self.children_spawned = start.checked_add(count).expect("too many children spawned from a SeedSequence");
+        (start..self.children_spawned)
+            .map(|index| {
+                let mut spawn_key = self.spawn_key.clone();
+                spawn_key.push(index);
+                SeedSequence::with_spawn_key(self.entropy.clone(), self.entropy_bytes, spawn_key)
+            })
+            .collect()
+    }
+
+    /// Fills `dest` with words generated from a pool of `dest.len()` words (or the default size if that is larger).
+    pub fn generate_u32(&self, dest: &mut [u32]) {
+        let pool = self.pool(dest.len());
+        let mut hash_const = INIT_B;
+        for (word, &pool) in dest.iter_mut().zip(pool.iter()) {
+            let mut value = pool ^ hash_const;
+            hash_const = hash_const.wrapping_mul(MULT_B);
+            value = value.wrapping_mul(hash_const);
+            *word = value ^ (value >> XSHIFT);
+        }
+    }
+
+    /// Fills `dest` with words generated from the pool. Each word consists of two 32-bit words, low half first.
+    pub fn generate_u64(&self, dest: &mut [u64]) {
+        let mut words = vec![0; dest.len() * 2];
+        self.generate_u32(&mut words);
+        for (word, pair) in dest.iter_mut().zip(words.chunks_exact(2)) {
+            *word = pair[0] as u64 | (pair[1] as u64) << 32;
+        }
+    }
+
+    fn pool(&self, size: usize) -> Vec<u32> {
+        // The lengths come first, so that no two inputs hash the same words, even after padding with zeros.
+        let bytes = self.entropy_bytes;
+        let mut assembled = vec![bytes as u32, (bytes >> 32) as u32, self.spawn_key.len() as u32];
+        assembled.extend_from_slice(&self.entropy);
+        assembled.extend_from_slice(&self.spawn_key);
+        mix_entropy(&assembled, size.max(POOL_SIZE))
+    }
+}
+
+fn hashmix(value: u32, hash_const: &mut u32) -> u32 {
+    let mut value = value ^ *hash_const;
+    *hash_const = hash_const.wrapping_mul(MULT_A);
+    value = value.wrapping_mul(*hash_const);
+    value ^ (value >> XSHIFT)
+}
+
+fn mix(x: u32, y: u32) -> u32 {
+    let result = MIX_MULT_L.wrapping_mul(x).wrapping_sub(MIX_MULT_R.wrapping_mul(y));
+    result ^ (result >> XSHIFT)
+}
+
+fn mix_entropy(entropy: &[u32], pool_size: usize) -> Vec<u32> {
+    let mut hash_const = INIT_A;
+    let mut pool = vec![0; pool_size];
+    for (i, word) in pool.iter_mut().enumerate() {
+        *word = hashmix(entropy.get(i).copied().unwrap_or(0), &mut hash_const);
+    }
+    // Every pool word is mixed into every other one, so all of them depend on every input word.
+    for src in 0..pool_size {
+        for dst in 0..pool_size {
+            if src != dst {
+                let hashed = hashmix(pool[src], &mut hash_const);
+                pool[dst] = mix(pool[dst], hashed);
+            }
+        }
+    }
+    for &value in entropy.iter().skip(pool_size) {
+        for word in pool.iter_mut() {
+            *word = mix(*word, hashmix(value, &mut hash_const));
+        }
+    }
+    pool
+}
+
+// Custom Debug implementation that does not expose the entropy
+impl fmt::Debug for SeedSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SeedSequence")
+            .field("spawn_key", &self.spawn_key)
+            .field("children_spawned", &self.children_spawned)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Mwc128XXA32, Mwc256XXA64};
+    use rand_core::RngCore;
+
+    #[test]
+    fn test_avalanche() {
+        let mut base = [0u64; 4];
+        SeedSequence::new(&[1, 2, 3, 4, 5, 6]).generate_u64(&mut base);
+        for bit in 0..32 {
+            let mut flipped = [0u64; 4];
+            SeedSequence::new(&[1, 2, 3, 4, 5, 6 ^ (1 << bit)]).generate_u64(&mut flipped);
+            let changed: u32 = base.iter().zip(flipped.iter()).map(|(a, b)| (a ^ b).count_ones()).sum();
+            assert!(changed > 80 && changed < 176, "{}", changed);
+        }
+    }
+
+    #[test]
+    fn test_input_forms() {
+        let words = SeedSequence::new(&[1, 2, 3, 4]);
+        assert_eq!(words, SeedSequence::from_u64s(&[2 << 32 | 1, 4 << 32 | 3]));
+        assert_eq!(words, SeedSequence::from_bytes(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0]));
+        assert_ne!(words, SeedSequence::new(&[1, 2, 3, 5]));
+    }
+
+    #[test]
+    fn test_trailing_zeros() {
+        let generate = |seq: SeedSequence| {
+            let mut words = [0u64; 4];
+            seq.generate_u64(&mut words);
+            words
+        };
+        assert_ne!(generate(SeedSequence::from_bytes(b"seed")), generate(SeedSequence::from_bytes(b"seed\0\0")));
+        assert_ne!(generate(SeedSequence::new(&[1])), generate(SeedSequence::new(&[1, 0])));
+        assert_ne!(generate(SeedSequence::new(&[])), generate(SeedSequence::new(&[0])));
+        assert_ne!(generate(SeedSequence::new(&[1, 0])), generate(SeedSequence::new(&[1]).spawn(1).remove(0)));
+    }
+
+    #[test]
+    fn test_pool_size() {
+        // A large pool is mixed as well as the default one: every bit of the entropy affects every output word.
+        let entropy: Vec<u32> = (0..32).collect();
+        let mut base = [0u32; 32];
+        SeedSequence::new(&entropy).generate_u32(&mut base);
+        for bit in 0..32 {
+            let mut flipped_entropy = entropy.clone();
+            flipped_entropy[bit] ^= 1 << bit;
+            let mut flipped = [0u32; 32];
+            SeedSequence::new(&flipped_entropy).generate_u32(&mut flipped);
+            assert!(base.iter().zip(flipped.iter()).all(|(a, b)| a != b));
+            let changed: u32 = base.iter().zip(flipped.iter()).map(|(a, b)| (a ^ b).count_ones()).sum();
+            assert!(changed > 400 && changed < 624, "{}", changed);
+        }
+        // The default pool is used for small requests, so they are a prefix of each other.
+        let (mut two, mut four) = ([0u32; 2], [0u32; 4]);
+        SeedSequence::new(&entropy).generate_u32(&mut two);
+        SeedSequence::new(&entropy).generate_u32(&mut four);
+        assert_eq!(two, four[..2]);
+    }
+
+    #[test]
+    fn test_spawn() {
+        let mut root = SeedSequence::new(&[42]);
+        let mut children = root.spawn(2);
+        children.extend(root.spawn(1));
+        assert_eq!(children[2].spawn_key(), &[2]);
+        assert_eq!(children[1].clone().spawn(1)[0].spawn_key(), &[1, 0]);
+        let mut outputs: Vec<u64> = children.iter().map(|c| Mwc256XXA64::from_seed_sequence(c).next_u64()).collect();
+        outputs.push(Mwc256XXA64::from_seed_sequence(&root).next_u64());
+        outputs.sort_unstable();
+        outputs.dedup();
+        assert_eq!(outputs.len(), 4);
+        // Spawning is reproducible.
+        assert_eq!(SeedSequence::new(&[42]).spawn(3), children);
+    }
+
+    #[test]
+    #[should_panic(expected = "too many children")]
+    fn test_spawn_overflow() {
+        let mut root = SeedSequence::new(&[42]);
+        root.children_spawned = u32::MAX;
+        root.spawn(1);
+    }
+
+    #[test]
+    fn test_debug_hides_entropy() {
+        let mut root = SeedSequence::new(&[0x1234_5678]);
+        let child = root.spawn(2).pop().unwrap();
+        assert_eq!(format!("{:?}", child), "SeedSequence { spawn_key: [1], children_spawned: 0, .. }");
+        assert!(!format!("{:x?}", root).contains("12345678"));
+    }
+
+    #[test]
+    fn test_valid_states() {
+        for i in 0..100 {
+            let seq = SeedSequence::new(&[i]);
+            let rng = Mwc256XXA64::from_seed_sequence(&seq);
+            assert!(Mwc256XXA64::try_from_state(rng.to_state()).is_ok());
+            let rng = Mwc128XXA32::from_seed_sequence(&seq);
+            assert!(Mwc128XXA32::try_from_state(rng.to_state()).is_ok());
+        }
+    }
+}
@@ -0,0 +1,293 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serde support for the generators.
+//!
+//! Human readable formats use a single string such as `"Mwc256XXA64:1:<hex state>"` containing the algorithm,
+//! the format version and the words `x1, x2, x3, c` as zero padded hex. Binary formats use the tuple
+//! `(algorithm, version, x1, x2, x3, c)`. In both cases the state is validated when deserializing.
+//!
+//! The field based format written by earlier versions (a struct with the fields `x1, x2, x3, c`) is still
+//! accepted by self describing formats such as JSON. This is a breaking change for binary formats such as bincode,
+//! which cannot tell the two formats apart: states written by earlier versions must be read with the `legacy`
+//! module instead.
+
+use core::fmt;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{BufferedMwc256XXA64, Mwc128XXA32, Mwc256XXA64};
+
+const VERSION: u32 = 1;
+
+macro_rules! impl_serde {
+    ($name:ident, $word:ty) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let state = self.to_state();
+                if serializer.is_human_readable() {
+                    const DIGITS: usize = 2 * core::mem::size_of::<$word>();
+                    serializer.collect_str(&format_args!(
+                        "{}:{}:{:0w$x}{:0w$x}{:0w$x}{:0w$x}",
+                        stringify!($name), VERSION, state[0], state[1], state[2], state[3], w = DIGITS
+                    ))
+                } else {
+                    let mut tuple = serializer.serialize_tuple(6)?;
+                    tuple.serialize_element(stringify!($name))?;
+                    tuple.serialize_element(&VERSION)?;
+                    for word in state.iter() {
+                        tuple.serialize_element(word)?;
+                    }
+                    tuple.end()
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct StateVisitor;
+
+                impl StateVisitor {
+                    fn check_header<E: de::Error>(algorithm: &str, version: u32) -> Result<(), E> {
+                        if algorithm != stringify!($name) {
+                            return Err(E::invalid_value(de::Unexpected::Str(algorithm), &stringify!($name)));
+                        }
+                        if version != VERSION {
+                            return Err(E::custom(format_args!("unsupported format version {}", version)));
+                        }
+                        Ok(())
+                    }
+
+                    fn build<E: de::Error>(state: [$word; 4]) -> Result<$name, E> {
+                        $name::try_from_state(state).map_err(E::custom)
+                    }
+                }
+
+                impl<'de> Visitor<'de> for StateVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        write!(f, "a serialized {} state", stringify!($name))
+                    }
+
+                    fn visit_str<E: de::Error>(self, value: &str) -> Result<$name, E> {
+                        const DIGITS: usize = 2 * core::mem::size_of::<$word>();
+                        let mut parts = value.splitn(3, ':');
+                        let algorithm = parts.next().unwrap_or_default();
+                        let version = parts.next().and_then(|v| v.parse().ok());
+                        let hex = parts.next().unwrap_or_default();
+                        let version = version.ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))?;
+                        Self::check_header(algorithm, version)?;
+                        if hex.len() != 4 * DIGITS {
+                            return Err(E::invalid_length(hex.len(), &"four zero padded hex words"));
+                        }
+                        // `from_str_radix` would also accept a sign.
+                        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                            return Err(E::invalid_value(de::Unexpected::Str(hex), &"hex digits"));
+                        }
+                        let mut state = [0; 4];
+                        for (i, word) in state.iter_mut().enumerate() {
+                            let digits = &hex[i * DIGITS..(i + 1) * DIGITS];
+                            *word = <$word>::from_str_radix(digits, 16)
+                                .map_err(|_| E::invalid_value(de::Unexpected::Str(digits), &"a hex word"))?;
+                        }
+                        Self::build(state)
+                    }
+
+                    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<$name, A::Error> {
+                        let algorithm: String = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                        let version: u32 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                        Self::check_header(&algorithm, version)?;
+                        let mut state = [0; 4];
+                        for (i, word) in state.iter_mut().enumerate() {
+                            *word = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i + 2, &self))?;
+                        }
+                        Self::build(state)
+                    }
+
+                    // The field based format of earlier versions.
+                    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<$name, A::Error> {
+                        const FIELDS: [&str; 4] = ["x1", "x2", "x3", "c"];
+                        let mut state: [Option<$word>; 4] = [None; 4];
+                        while let Some(key) = map.next_key::<String>()? {
+                            let index = FIELDS.iter().position(|&f| f == key)
+                                .ok_or_else(|| de::Error::unknown_field(&key, &FIELDS))?;
+                            if state[index].is_some() {
+                                return Err(de::Error::duplicate_field(FIELDS[index]));
+                            }
+                            state[index] = Some(map.next_value()?);
+                        }
+                        let mut result = [0; 4];
+                        for i in 0..4 {
+                            result[i] = state[i].ok_or_else(|| de::Error::missing_field(FIELDS[i]))?;
+                        }
+                        Self::build(result)
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_any(StateVisitor)
+                } else {
+                    deserializer.deserialize_tuple(6, StateVisitor)
+                }
+            }
+        }
+    };
+}
+
+impl_serde!(Mwc256XXA64, u64);
+impl_serde!(Mwc128XXA32, u32);
+
+// The generator followed by the pending half output, if any.
+impl Serialize for BufferedMwc256XXA64 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.rng, &self.pending).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BufferedMwc256XXA64 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (rng, pending) = Deserialize::deserialize(deserializer)?;
+        Ok(BufferedMwc256XXA64 { rng, pending })
+    }
+}
+
+/// Reads and writes the field based format of earlier versions, for use with `#[serde(with = "...")]`.
+///
+/// This is needed to read binary blobs written by earlier versions, for example:
+///
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct Saved {
+///     #[serde(with = "pcg_mwc::serialization::legacy::mwc256xxa64")]
+///     rng: Mwc256XXA64,
+/// }
+/// ```
+///
+/// Once read, the generator can be written in the current format with its `Serialize` implementation.
+pub mod legacy {
+    macro_rules! impl_legacy {
+        ($module:ident, $name:ident, $word:ty, $label:literal) => {
+            #[doc = concat!("The field based format of `", $label, "`.")]
+            pub mod $module {
+                use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+                use crate::$name;
+
+                #[derive(Serialize, Deserialize)]
+                #[serde(rename = $label)]
+                struct Fields {
+                    x1: $word,
+                    x2: $word,
+                    x3: $word,
+                    c: $word,
+                }
+
+                /// Writes `rng` in the field based format.
+                pub fn serialize<S: Serializer>(rng: &$name, serializer: S) -> Result<S::Ok, S::Error> {
+                    let [x1, x2, x3, c] = rng.to_state();
+                    Fields { x1, x2, x3, c }.serialize(serializer)
+                }
+
+                /// Reads a generator in the field based format, rejecting invalid states.
+                pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$name, D::Error> {
+                    let Fields { x1, x2, x3, c } = Fields::deserialize(deserializer)?;
+                    $name::try_from_state([x1, x2, x3, c]).map_err(serde::de::Error::custom)
+                }
+            }
+        };
+    }
+
+    impl_legacy!(mwc256xxa64, Mwc256XXA64, u64, "Mwc256XXA64");
+    impl_legacy!(mwc128xxa32, Mwc128XXA32, u32, "Mwc128XXA32");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::{RngCore, SeedableRng};
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = Mwc256XXA64::seed_from_u64(1);
+        let json = serde_json::to_string(&rng).unwrap();
+        assert!(json.starts_with("\"Mwc256XXA64:1:"));
+        let mut from_json: Mwc256XXA64 = serde_json::from_str(&json).unwrap();
+        let mut from_binary: Mwc256XXA64 = bincode::deserialize(&bincode::serialize(&rng).unwrap()).unwrap();
+        let expected = rng.next_u64();
+        assert_eq!(from_json.next_u64(), expected);
+        assert_eq!(from_binary.next_u64(), expected);
+
+        let mut rng = Mwc128XXA32::seed_from_u64(1);
+        let mut from_json: Mwc128XXA32 = serde_json::from_str(&serde_json::to_string(&rng).unwrap()).unwrap();
+        let mut from_binary: Mwc128XXA32 = bincode::deserialize(&bincode::serialize(&rng).unwrap()).unwrap();
+        let expected = rng.next_u32();
+        assert_eq!(from_json.next_u32(), expected);
+        assert_eq!(from_binary.next_u32(), expected);
+    }
+
+    #[test]
+    fn test_buffered_round_trip() {
+        let mut rng = BufferedMwc256XXA64::seed_from_u64(1);
+        rng.next_u32();
+        let mut from_json: BufferedMwc256XXA64 = serde_json::from_str(&serde_json::to_string(&rng).unwrap()).unwrap();
+        let mut from_binary: BufferedMwc256XXA64 = bincode::deserialize(&bincode::serialize(&rng).unwrap()).unwrap();
+        let expected = rng.next_u32();
+        assert_eq!(from_json.next_u32(), expected);
+        assert_eq!(from_binary.next_u32(), expected);
+    }
+
+    #[test]
+    fn test_legacy_fields() {
+        let rng: Mwc256XXA64 = serde_json::from_str(r#"{"x1":1,"x2":2,"x3":3,"c":4}"#).unwrap();
+        assert_eq!(rng.to_state(), [1, 2, 3, 4]);
+        let rng: Mwc128XXA32 = serde_json::from_str(r#"{"c":4,"x3":3,"x2":2,"x1":1}"#).unwrap();
+        assert_eq!(rng.to_state(), [1, 2, 3, 4]);
+        assert!(serde_json::from_str::<Mwc256XXA64>(r#"{"x1":1,"x2":2,"x3":3}"#).is_err());
+    }
+
+    #[test]
+    fn test_legacy_binary() {
+        // The format derived by earlier versions.
+        #[derive(Serialize)]
+        struct Old {
+            x1: u64,
+            x2: u64,
+            x3: u64,
+            c: u64,
+        }
+        #[derive(Serialize, Deserialize)]
+        struct Saved {
+            #[serde(with = "legacy::mwc256xxa64")]
+            rng: Mwc256XXA64,
+        }
+        let old = bincode::serialize(&Old { x1: 1, x2: 2, x3: 3, c: 4 }).unwrap();
+        assert!(bincode::deserialize::<Mwc256XXA64>(&old).is_err());
+        let saved: Saved = bincode::deserialize(&old).unwrap();
+        assert_eq!(saved.rng.to_state(), [1, 2, 3, 4]);
+        assert_eq!(bincode::serialize(&saved).unwrap(), old);
+        let zeros = bincode::serialize(&Old { x1: 0, x2: 0, x3: 0, c: 0 }).unwrap();
+        assert!(bincode::deserialize::<Saved>(&zeros).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        assert!(serde_json::from_str::<Mwc256XXA64>(r#"{"x1":0,"x2":0,"x3":0,"c":0}"#).is_err());
+        let zeros = format!("\"Mwc128XXA32:1:{}\"", "0".repeat(32));
+        assert!(serde_json::from_str::<Mwc128XXA32>(&zeros).is_err());
+        let json = serde_json::to_string(&Mwc128XXA32::new(1, 2)).unwrap();
+        assert!(serde_json::from_str::<Mwc256XXA64>(&json).is_err());
+        assert!(serde_json::from_str::<Mwc128XXA32>(&json.replace(":1:", ":2:")).is_err());
+        let hex = json.rsplit(':').next().unwrap();
+        let signed = json.replace(hex, &format!("+{}", &hex[1..]));
+        assert!(serde_json::from_str::<Mwc128XXA32>(&signed).is_err());
+        assert!(serde_json::from_str::<Mwc128XXA32>(&json.replace(hex, &format!("é{}", &hex[2..]))).is_err());
+        let rng = Mwc256XXA64::new(1, 2);
+        let binary = bincode::serialize(&rng).unwrap();
+        assert!(bincode::deserialize::<Mwc128XXA32>(&binary).is_err());
+    }
+}
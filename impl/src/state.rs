@@ -0,0 +1,28 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::fmt;
+
+/// The error returned when importing a state which is not a valid MWC state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidState {
+    /// The carry is greater than or equal to the multiplier, so the state is not reachable from any other state.
+    CarryTooLarge,
+    /// The state is all zeros or all ones (with the carry being one less than the multiplier).
+    /// Both represent the residue zero of the equivalent MCG which is a fixed point of the generator.
+    FixedPoint,
+}
+
+impl fmt::Display for InvalidState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidState::CarryTooLarge => write!(f, "the carry must be less than the multiplier"),
+            InvalidState::FixedPoint => write!(f, "the state is a fixed point of the generator"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidState {}
@@ -0,0 +1,33 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers for the `fill_*_uninit` methods.
+
+use core::mem::MaybeUninit;
+
+/// Views an initialized slice as possibly uninitialized, so that it can be passed to a `fill_*_uninit` method.
+///
+/// This is only sound because those methods never write uninitialized values.
+#[inline(always)]
+pub(crate) fn as_uninit<T>(slice: &mut [T]) -> &mut [MaybeUninit<T>] {
+    // Safety: `MaybeUninit<T>` has the same layout as `T`.
+    unsafe { &mut *(slice as *mut [T] as *mut [MaybeUninit<T>]) }
+}
+
+/// # Safety
+/// Every element of `slice` must have been initialized.
+#[inline(always)]
+pub(crate) unsafe fn assume_init<T>(slice: &mut [MaybeUninit<T>]) -> &mut [T] {
+    &mut *(slice as *mut [MaybeUninit<T>] as *mut [T])
+}
+
+/// Initializes `dest` with the first `dest.len()` elements of `src`.
+#[inline(always)]
+pub(crate) fn write_slice<T: Copy>(dest: &mut [MaybeUninit<T>], src: &[T]) {
+    for (d, &s) in dest.iter_mut().zip(src.iter()) {
+        d.write(s);
+    }
+}
@@ -1,3 +1,5 @@
+use pcg_mwc::Mwc;
+
 
 pub struct PCG {
     state: u32,
@@ -32,30 +34,4 @@ impl Default for PCG {
 // 57984 - lag-4 REALLY BAD spectra
 const MULTIPLIER: u16 = 39273; //Lag-3 good spectra
 
-pub struct Gen16 {
-    pub(crate) x1: u16,
-    pub(crate) x2: u16,
-    pub(crate) x3: u16,
-    pub(crate) c: u16,
-}
-
-impl Gen16 {
-
-    pub(crate) fn next(&mut self) -> u16 {
-        // prepare the MCG for the next round
-        let (low, hi) = multiply(self.x3);
-        let result = (self.x3 ^ self.x2).wrapping_add(self.x1 ^ hi);
-        let (x1, b) = low.overflowing_add(self.c);
-        self.x3 = self.x2;
-        self.x2 = self.x1;
-        self.x1 = x1;
-        self.c = hi.wrapping_add(b as u16);
-        result
-    }
-}
-
-#[inline(always)]
-fn multiply(val: u16) -> (u16, u16) {
-    let t = (val as u32).wrapping_mul(MULTIPLIER as u32);
-    return (t as u16, (t >> 16) as u16);
-}
\ No newline at end of file
+pub type Gen16 = Mwc<u16, 3, { MULTIPLIER as u64 }>;
@@ -1,3 +1,5 @@
+use pcg_mwc::Mwc;
+
 pub trait Gen {
     fn reset(&mut self);
 
@@ -87,44 +89,26 @@ impl Gen for Xoshiro32 {
 // This is the default multiplier used by MWC.
 pub(crate) const MULTIPLIER: u8 = 228;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Clone)]
 pub struct Mcg32_8 {
-    pub(crate) x1: u8,
-    pub(crate) x2: u8,
-    pub(crate) x3: u8,
-    pub(crate) c: u8,
+    pub(crate) rng: Mwc<u8, 3, { MULTIPLIER as u64 }>,
 }
 
 impl Default for Mcg32_8 {
     fn default() -> Self {
         Mcg32_8 {
-            x1: 123,
-            x2: 34,
-            x3: 56,
-            c: 78,
+            rng: Mwc::try_from_words([123, 34, 56], 78).unwrap(),
         }
     }
 }
 
 impl Gen for Mcg32_8 {
     fn reset(&mut self) {
-        self.x1 = 123;
-        self.x2 = 34;
-        self.x3 = 56;
-        self.c = 78;
+        *self = Mcg32_8::default();
     }
 
     fn next(&mut self) -> u8 {
-        // prepare the MCG for the next round
-        let t = (self.x3 as u16).wrapping_mul(MULTIPLIER as u16);
-        let (low, hi) = (t as u8, (t >> 8) as u8);
-        let result = (self.x3 ^ self.x2).wrapping_add(self.x1 ^ hi);
-        let (x1, b) = low.overflowing_add(self.c);
-        self.x3 = self.x2;
-        self.x2 = self.x1;
-        self.x1 = x1;
-        self.c = hi.wrapping_add(b as u8);
+        let [result] = self.rng.gen_array();
         result
     }
 }
-
@@ -19,6 +19,108 @@ fn is_good_prime(candidate: &u64) -> bool {
     return true;
 }
 
+// fff02b36e685963b passes for 128 and is used by the lag-2 Mwc192XXA64. a7298353f4250d13 is used by the lag-4 Mwc320XXA64.
+// ffe54e9b558201ab passes for 960 and is used by the lag-15 Mwc1024XXA64.
+// ffbb4292740bcf7d passes for 384 and is used by the 128 bit word, lag-3 Mwc512XXA128.
+pub(crate) fn check_sibling_multipliers() {
+    let multipliers = [
+        (0xfff0_2b36_e685_963bu64, 128u32),
+        (0xa729_8353_f425_0d13, 256),
+        (0xffe5_4e9b_5582_01ab, 960),
+        (0xffbb_4292_740b_cf7d, 384),
+    ];
+    for &(multiplier, shift) in &multipliers {
+        let value = BigUint::from(multiplier);
+        assert!(glass_pumpkin::safe_prime::check(&value.shl(shift).sub(1u32)));
+        println!("2^{}: 0x{:x}", shift, multiplier);
+    }
+}
+
+// Searches random multipliers with the same filters as `gen_64_bit_candidates`, for a single modulus
+// `multiplier * 2^shift - 1`. This is much faster than enumerating when the shift is large.
+// Outputs for 384:
+// 0xffbb4292740bcf7d
+// 0xffbb4292741bf38b
+// 0xffbb4292745d2035
+// 0xffbb4292746dd6f5
+pub(crate) fn gen_64_bit_candidates_for_shift(shift: u32, count: usize) -> Vec<u64> {
+    let mut candidates = Vec::new();
+    while candidates.len() < count {
+        let mut batch: Vec<u64> = (0..1 << 16).into_par_iter().filter_map(|_| {
+            let v = rand::random::<u64>() | 0xfe00_0000_0000_0000;
+            if v % 3 != 0 || ((v & 7 != 5) && (v & 7 != 3)) || !no_dups(v) {
+                return None;
+            }
+            if !glass_pumpkin::prime::check(&BigUint::from(v / 3)) {
+                return None;
+            }
+            if !glass_pumpkin::safe_prime::check(&BigUint::from(v).shl(shift).sub(1u32)) {
+                return None;
+            }
+            println!("0x{:x}", v);
+            Some(v)
+        }).collect();
+        candidates.append(&mut batch);
+    }
+    candidates
+}
+
+// The complementary MWC generators use the prime modulus `multiplier * 2^(word_bits * lag) + 1`, and their period is
+// the order of `2^word_bits` modulo it. 2 is a square modulo any prime of this form, so the largest possible order is
+// `(p - 1) / (2 * word_bits)`, which every state (there are no fixed points) cycles through.
+fn is_good_cmwc_multiplier(sieve: &primal::Sieve, multiplier: u64, word_bits: u32, lag: u32) -> bool {
+    let one = BigUint::from(1u32);
+    let p = BigUint::from(multiplier).shl(word_bits * lag) + &one;
+    if !glass_pumpkin::prime::check(&p) {
+        return false;
+    }
+    let b = one.clone().shl(word_bits);
+    let order = (&p - &one) / (2 * word_bits);
+    if b.modpow(&order, &p) != one {
+        return false;
+    }
+    let mut factors: Vec<u64> = sieve.factor(multiplier as usize).unwrap().iter().map(|&(q, _)| q as u64).collect();
+    factors.push(2);
+    factors.iter().all(|&q| b.modpow(&(&order / q), &p) != one)
+}
+
+// Outputs for 64 bit words and lag 3:
+// 0xfee9a0fd5e74b050
+// 0xffafd1cbb35aadb7
+// 0xfebe237bad078f03
+// Outputs for 32 bit words and lag 3:
+// 0xff33e968
+// 0xff6d27a2
+// 0xff56955d
+pub(crate) fn gen_cmwc_candidates(word_bits: u32, lag: u32, count: usize) -> Vec<u64> {
+    let sieve = primal::Sieve::new(u32::max_value() as usize);
+    let mut candidates = Vec::new();
+    while candidates.len() < count {
+        let mut batch: Vec<u64> = (0..1 << 16).into_par_iter().filter_map(|_| {
+            let v = (rand::random::<u64>() >> (64 - word_bits)) | (0xfe << (word_bits - 8));
+            if !no_dup_bytes(&v.to_le_bytes()[..word_bits as usize / 8]) {
+                return None;
+            }
+            if !is_good_cmwc_multiplier(&sieve, v, word_bits, lag) {
+                return None;
+            }
+            println!("0x{:x}", v);
+            Some(v)
+        }).collect();
+        candidates.append(&mut batch);
+    }
+    candidates
+}
+
+// ffafd1cbb35aadb7 is used by Cmwc256XXA64 and ff56955d is used by Cmwc128XXA32.
+pub(crate) fn check_cmwc_multipliers() {
+    let sieve = primal::Sieve::new(u32::max_value() as usize);
+    assert!(is_good_cmwc_multiplier(&sieve, 0xffaf_d1cb_b35a_adb7, 64, 3));
+    assert!(is_good_cmwc_multiplier(&sieve, 0xff56_955d, 32, 3));
+    // Small ones for comparing the period structure against the MWC generator in the tests of `cmwc.rs`.
+    assert!(is_good_cmwc_multiplier(&sieve, 12, 8, 2));
+}
+
 pub(crate) fn gen_8_bit_candidates() {
     for v in 1..=255u8 {
         let value = BigUint::from(v);
@@ -75,10 +177,13 @@ pub(crate) fn gen_32_bit_candidates() {
 }
 
 fn no_dups(value: u64) -> bool {
-    let bytes = value.to_ne_bytes();
-    for &byte in &bytes {
+    no_dup_bytes(&value.to_ne_bytes())
+}
+
+fn no_dup_bytes(bytes: &[u8]) -> bool {
+    for &byte in bytes {
         let mut count = 0;
-        for &to_match in &bytes {
+        for &to_match in bytes {
             if to_match == byte {
                 count+=1;
             }